@@ -0,0 +1,3 @@
+mod sudoku;
+
+pub use sudoku::{algorithm, bitboard, dlx, entity, guess, rulers};
@@ -1,16 +1,28 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Display,
+    io::Write,
+    panic::AssertUnwindSafe,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
 use sudoku::{
-    algorithm::CandidateMatrix,
+    algorithm::{hint, rate, CandidateMatrix, HintLevel, Pipeline},
+    bitboard::BitboardSolver,
+    dlx::DlxSolver,
     entity::{
         is_sudoku_value, SudokuMatrixValue, SudokuValueType, SQUARE_INNER_LEN, SQUARE_OUTER_LEN,
         SUDOKU_UNKNOWN,
     },
-    guess::SudokuSolver,
+    guess::Solver,
     rulers::init,
 };
 
-mod sudoku;
+/// 需要猜测时最多展示的解的个数
+const MAX_SHOWN_SOLUTIONS: usize = 100;
 
-fn from_string(s: &String) -> SudokuMatrixValue {
+fn from_string(s: &str) -> SudokuMatrixValue {
     assert!(s.is_ascii());
     let mut matrix = SudokuMatrixValue::new();
     let (mut row, mut col) = (0, 0);
@@ -31,6 +43,22 @@ fn from_string(s: &String) -> SudokuMatrixValue {
     matrix
 }
 
+/// 批量求解的一行 须恰好 81 个 0-9 或 . 不含其他字符 首尾空白忽略
+fn parse_line(line: &str) -> Option<SudokuMatrixValue> {
+    let line = line.trim();
+    if line.len() != SQUARE_OUTER_LEN * SQUARE_OUTER_LEN
+        || !line.chars().all(|c| c == '.' || c.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut matrix = SudokuMatrixValue::new();
+    for (i, c) in line.chars().enumerate() {
+        matrix.matrix[i / SQUARE_OUTER_LEN][i % SQUARE_OUTER_LEN] =
+            c.to_digit(10).unwrap_or(SUDOKU_UNKNOWN as u32) as SudokuValueType;
+    }
+    Some(matrix)
+}
+
 fn show(matrix: &SudokuMatrixValue) {
     println!();
     for (i, line) in matrix.matrix.iter().enumerate() {
@@ -52,7 +80,7 @@ fn show_can(can: &CandidateMatrix) {
             for (j, c) in line.iter().enumerate() {
                 for col in 0..SQUARE_INNER_LEN {
                     let value = row * SQUARE_INNER_LEN + col;
-                    if c.can[value] {
+                    if c.has(value) {
                         print!("{} ", value + 1);
                     } else {
                         print!("  ");
@@ -80,17 +108,236 @@ fn show_can(can: &CandidateMatrix) {
     println!();
 }
 
+/// 批量求解时每道题的结论
+enum BatchStatus {
+    SolvedLogically,
+    NeededSearch,
+    NoSolution,
+    MultipleSolutions,
+    /// 该行不是一道题
+    InvalidInput,
+    /// 求解过程中 panic 记录其信息与位置 不影响其他题目
+    Failed(String),
+}
+
+impl Display for BatchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            BatchStatus::SolvedLogically => "solved logically",
+            BatchStatus::NeededSearch => "needed search",
+            BatchStatus::NoSolution => "no solution",
+            BatchStatus::MultipleSolutions => "multiple solutions",
+            BatchStatus::InvalidInput => "invalid input",
+            BatchStatus::Failed(message) => return write!(f, "failed: {}", message),
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// 先以技巧推演 无法完成时再以位棋盘搜索 唯一解时给出答案
+/// 假设唯一解时 推演出现矛盾可能只是因为解不唯一 改为从原题搜索判断
+fn solve_one(
+    sudoku: SudokuMatrixValue,
+    pipeline: &Pipeline,
+    assume_unique: bool,
+) -> (BatchStatus, Option<SudokuMatrixValue>) {
+    let mut can = CandidateMatrix::from(sudoku);
+    if pipeline.run(&mut can).is_err() {
+        if !assume_unique {
+            return (BatchStatus::NoSolution, None);
+        }
+        can = CandidateMatrix::from(sudoku);
+    } else if can.finished() {
+        return (BatchStatus::SolvedLogically, Some(can.into()));
+    }
+    let mut solver = BitboardSolver::from(can);
+    match solver.count_solutions(2) {
+        0 => (BatchStatus::NoSolution, None),
+        1 => (
+            BatchStatus::NeededSearch,
+            solver.get_all_possible_sudoku().first().copied(),
+        ),
+        _ => (BatchStatus::MultipleSolutions, None),
+    }
+}
+
+thread_local! {
+    /// 当前线程是否正在批量求解某道题 此时 panic 由 solve_caught 汇报
+    static IS_SOLVING: Cell<bool> = const { Cell::new(false) };
+    /// 批量求解时最近一次 panic 的信息与位置
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// 批量求解时 panic 不直接打印到 stderr 而是记下信息与位置 其余 panic 仍交给原有的 hook
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !IS_SOLVING.get() {
+            default_hook(info);
+            return;
+        }
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("unknown panic"));
+        let location = info.location().map_or(String::new(), |location| {
+            format!(" at {}:{}", location.file(), location.line())
+        });
+        // 结果按行以制表符分隔输出 信息中的空白统一为空格
+        let message = message.split_whitespace().collect::<Vec<_>>().join(" ");
+        LAST_PANIC.set(Some(format!("{}{}", message, location)));
+    }));
+}
+
+/// 求解一道题 panic 时给出 Failed 及其信息 而不中断整个批次
+fn solve_caught(
+    sudoku: SudokuMatrixValue,
+    pipeline: &Pipeline,
+    assume_unique: bool,
+) -> (BatchStatus, Option<SudokuMatrixValue>) {
+    IS_SOLVING.set(true);
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        solve_one(sudoku, pipeline, assume_unique)
+    }));
+    IS_SOLVING.set(false);
+    result.unwrap_or_else(|_| {
+        let message = LAST_PANIC
+            .take()
+            .unwrap_or_else(|| String::from("unknown panic"));
+        (BatchStatus::Failed(message), None)
+    })
+}
+
+/// 每行一道题 以 . 或 0 表示未知 空行忽略 格式不符的行单独报告 不影响其他题目
+/// 多线程求解 按输入顺序输出：行号 结论 耗时 答案
+/// 默认仅以唯一位置与唯一候选值推演 其余交给位棋盘搜索 全部技巧与假设推演需显式开启
+fn run_batch(
+    path: &str,
+    assume_unique: bool,
+    is_full_logic: bool,
+    is_exhaustive: bool,
+) -> std::io::Result<()> {
+    let input_data = std::fs::read_to_string(path)?;
+    let puzzles: Vec<(usize, Option<SudokuMatrixValue>)> = input_data
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_id, line)| (line_id + 1, parse_line(line)))
+        .collect();
+
+    init();
+    install_panic_hook();
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut results: Vec<(usize, BatchStatus, Option<SudokuMatrixValue>, Duration)> =
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let pipeline = if is_exhaustive {
+                            Pipeline::exhaustive(assume_unique)
+                        } else if is_full_logic {
+                            Pipeline::standard(assume_unique)
+                        } else {
+                            Pipeline::singles()
+                        };
+                        let mut solved = Vec::new();
+                        // 逐题领取 避免难题集中在同一线程
+                        while let Some((line_id, sudoku)) =
+                            puzzles.get(next.fetch_add(1, Ordering::Relaxed))
+                        {
+                            let start = Instant::now();
+                            let (status, solution) = match sudoku {
+                                Some(sudoku) => solve_caught(*sudoku, &pipeline, assume_unique),
+                                None => (BatchStatus::InvalidInput, None),
+                            };
+                            solved.push((*line_id, status, solution, start.elapsed()));
+                        }
+                        solved
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+    results.sort_by_key(|(line_id, ..)| *line_id);
+
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    let mut counts = [0; 6];
+    for (line_id, status, solution, elapsed) in results.iter() {
+        counts[match status {
+            BatchStatus::SolvedLogically => 0,
+            BatchStatus::NeededSearch => 1,
+            BatchStatus::NoSolution => 2,
+            BatchStatus::MultipleSolutions => 3,
+            BatchStatus::InvalidInput => 4,
+            BatchStatus::Failed(_) => 5,
+        }] += 1;
+        let solution = match solution {
+            Some(matrix) => matrix
+                .matrix
+                .iter()
+                .flatten()
+                .map(|value| value.to_string())
+                .collect(),
+            None => String::from("-"),
+        };
+        writeln!(
+            out,
+            "{}\t{}\t{:.3}ms\t{}",
+            line_id,
+            status,
+            elapsed.as_secs_f64() * 1000.0,
+            solution
+        )?;
+    }
+    writeln!(
+        out,
+        "{} puzzles in {:.3}s on {} thread(s): {} solved logically, {} needed search, {} no solution, {} multiple solutions, {} invalid input, {} failed",
+        results.len(),
+        started.elapsed().as_secs_f64(),
+        workers,
+        counts[0],
+        counts[1],
+        counts[2],
+        counts[3],
+        counts[4],
+        counts[5]
+    )?;
+    Ok(())
+}
+
 /// > Get-Content .\input | .\sudoku.exe
 fn main() -> std::io::Result<()> {
     let mut is_print_help = false;
     let mut is_debug_mode = false;
     let mut is_show_candi = false;
-    for ele in std::env::args().skip(0) {
-        match &ele as &str {
+    let mut assume_unique = false;
+    let mut is_hint_only = false;
+    let mut is_rate_only = false;
+    let mut is_singles_only = false;
+    let mut is_full_logic = false;
+    let mut is_exhaustive = false;
+    let mut batch_path = None;
+    let args: Vec<String> = std::env::args().collect();
+    for (i, ele) in args.iter().enumerate() {
+        match ele as &str {
             "h" => is_print_help = true,
             "help" => is_print_help = true,
             "debug" => is_debug_mode = true,
             "candi" => is_show_candi = true,
+            "unique" => assume_unique = true,
+            "hint" => is_hint_only = true,
+            "rate" => is_rate_only = true,
+            "singles" => is_singles_only = true,
+            "full" => is_full_logic = true,
+            "exhaustive" => is_exhaustive = true,
+            "batch" => batch_path = args.get(i + 1),
             _ => {}
         }
     }
@@ -110,11 +357,23 @@ fn main() -> std::io::Result<()> {
         println!();
         println!("option:");
         println!("help / h -> to print help");
-        println!("debug -> to show SudokuMatrix each step");
+        println!("debug -> to show each step with its technique and explanation");
         println!("candi -> to show CandidateMatrix each step, only if debug");
+        println!("unique -> to assume the sudoku has only one solution");
+        println!("hint -> to show only the next easiest step, from region to full move");
+        println!("rate -> to show only the difficulty rating");
+        println!("singles -> to use only hidden and naked singles");
+        println!("batch <file> -> to solve a file with one sudoku per line on all cores,");
+        println!("                using only singles before the bitboard search");
+        println!("full -> to use all logical techniques in batch mode, much slower");
+        println!("exhaustive -> to also use forcing chains and nishio, may take very long");
         return Ok(());
     }
 
+    if let Some(path) = batch_path {
+        return run_batch(path, assume_unique, is_full_logic, is_exhaustive);
+    }
+
     #[cfg(debug_assertions)]
     let input_data = {
         let mut path = std::env::current_dir()?;
@@ -136,35 +395,86 @@ fn main() -> std::io::Result<()> {
 
     init();
     let mut can = CandidateMatrix::from(sudoku);
-    loop {
-        if can.finished() {
-            println!("The only certain result is:");
-            show(&can.into());
-            return Ok(());
+    if is_hint_only {
+        match hint(&can) {
+            Some(step) => {
+                for level in [HintLevel::Region, HintLevel::Technique, HintLevel::Move] {
+                    println!("{}", step.nudge(level));
+                }
+            }
+            None => println!("No hint available."),
         }
-
-        let origin = can.clone();
-        can.evolution();
-        can.evolution_by_position_mutex();
-        can.evolution_by_check_position();
-        if origin == can {
-            break;
+        return Ok(());
+    }
+    if is_rate_only {
+        match rate(&can) {
+            Ok(rating) => println!("Difficulty: {}", rating),
+            Err(err) => println!("The sudoku has no solution: {}", err),
         }
-
+        return Ok(());
+    }
+    let pipeline = if is_singles_only {
+        Pipeline::singles()
+    } else if is_exhaustive {
+        Pipeline::exhaustive(assume_unique)
+    } else {
+        Pipeline::standard(assume_unique)
+    };
+    let result = pipeline.run_with(&mut can, |step, can| {
         if is_debug_mode {
-            show(&can.into());
+            println!("{}", step.explanation());
         }
         if is_show_candi {
-            show_can(&can);
+            show_can(can);
+        }
+    });
+    if let Err(err) = result {
+        // 假设唯一解的技巧在多解的题目上也会推出矛盾
+        let has_solution = assume_unique
+            && BitboardSolver::from(CandidateMatrix::from(sudoku)).count_solutions(2) > 0;
+        if !has_solution {
+            println!("The sudoku has no solution: {}", err);
+            return Ok(());
         }
+        println!(
+            "The sudoku can not be solved assuming a unique solution: {}",
+            err
+        );
+        can = CandidateMatrix::from(sudoku);
+    } else if can.finished() {
+        println!("The only certain result is:");
+        show(&can.into());
+        return Ok(());
     }
 
     println!("All possible result is:");
-    let mut soler = SudokuSolver::from(can);
-    soler.solver_possible();
+    let mut soler = DlxSolver::from(can);
+    let count = soler.count_solutions(MAX_SHOWN_SOLUTIONS);
 
     for matrix in soler.get_all_possible_sudoku() {
         show(matrix);
     }
+    if count >= MAX_SHOWN_SOLUTIONS {
+        println!("Only the first {} results are shown.", MAX_SHOWN_SOLUTIONS);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let line = format!("  5.3{}  ", "0".repeat(78));
+        let sudoku = parse_line(&line).unwrap();
+        assert_eq!(sudoku.matrix[0][..3], [5, 0, 3]);
+        assert_eq!(sudoku.matrix[8][8], 0);
+
+        // 不足 81 个 多余字符 非 ASCII 均不是一道题
+        assert!(parse_line(&"1".repeat(80)).is_none());
+        assert!(parse_line(&"1".repeat(82)).is_none());
+        assert!(parse_line(&format!("{}x", "1".repeat(80))).is_none());
+        assert!(parse_line(&format!("{}数", "1".repeat(80))).is_none());
+    }
+}
@@ -2,3 +2,8 @@ pub mod entity;
 pub mod rulers;
 pub mod algorithm;
 pub mod guess;
+pub mod dlx;
+pub mod bitboard;
+
+#[cfg(test)]
+mod fixture;
@@ -4,17 +4,68 @@
 //! 1、某一位置 剩余唯一候选数值  -- 可确定值
 //! 2、某一分区 某一数值 仅有一个位置可选  -- 可确定值
 //! 3、某一分区 某一数值 多个位置可选 所有位置均在另一分区  -- 可将另一分区的其他位置标记该值的黑名单
-//! 4、某一分区 多个数值 多个位置可选 数值和位置是互斥关系  -- 可将这些位置的其他候选值标记黑名单
+//! 4、某一分区 多个数值 可选位置合计与数值数相同 数值和位置是互斥关系  -- 可将这些位置的其他候选值标记黑名单
+//! 5、某一分区 多个位置 候选数值合计与位置数相同 位置和数值是互斥关系  -- 可将分区其他位置的这些数值标记黑名单
+//! 6、某一数值 多行（列）的位置 全部落在同样数量的列（行）内  -- 可将这些列（行）其他位置标记该值的黑名单
+//! 7、同 6 但有多余位置（鱼鳍）集中在一个九宫格内  -- 可将这些列（行）在该九宫格内的其他位置标记该值的黑名单
+//! 8、枢纽与两个双值钳子 三者候选值共三个 钳子共有一个数值  -- 可将同时可见所有含该值位置的其他位置标记该值的黑名单
+//! 9、某一数值 两个分区各仅有两个位置可选 且两者各有一端互相可见  -- 可将同时可见另外两端的位置标记该值的黑名单
+//! 10、某一数值 九宫格内位置均在一行一列上 配合其他分区仅有两个位置可选  -- 可排除交叉位置的该值
+//! 11、两个相同的双值格 某一数值的强链两端分别可见二者  -- 可将同时可见二者的位置标记另一数值的黑名单
+//! 12、某一数值的强链交替染色 同色同真同假  -- 可排除同色互见的颜色 以及同时可见两种颜色的位置
+//! 13、强弱交替的推理链 首尾至少一个为真  -- 可将与首尾均互斥的候选标记黑名单
+//! 14、N 个位置 N+1 个候选值的待定数组 通过互斥的公共候选相连  -- 可排除必然出现在某一数组内的数值
+//! 15、九宫格与行（列）相交处的位置 配合两部分其余位置组成数组 两部分候选值不相交  -- 可分别排除行（列）与九宫格其他位置的数值
+//! 16、某一数值 每行每列每个九宫格各一个位置的全部模板中 与候选值不冲突的模板  -- 不在任何模板中的位置可标记黑名单 在所有模板中的位置可确定值
+//! 17、某一位置的全部候选值 或某一分区某一数值的全部位置 分别假设并推演  -- 所有情况下均被排除的候选值可标记黑名单
+//! 18、假设某一候选值成立 以基础技巧推演后出现矛盾  -- 该候选值可标记黑名单
+//! 假设唯一解的技巧：
+//! 19、唯一矩形 四个位置若仅剩两个相同的候选值则解不唯一  -- 可排除导致该结构的候选值
+//! 20、除一个三值位置外均为双值位置 去掉某一候选值则解不唯一  -- 该候选值可确定
+
+mod als;
+mod chain;
+mod coloring;
+mod contradiction;
+mod fish;
+mod forcing;
+mod hint;
+mod nishio;
+mod overlay;
+mod pipeline;
+mod rating;
+mod step;
+mod strong_link;
+mod sue_de_coq;
+mod uniqueness;
+mod wing;
+
+use std::ops::RangeInclusive;
+
+pub use contradiction::Contradiction;
+pub use forcing::{ForcingChainStrategy, DEFAULT_FORCING_DEPTH};
+pub use hint::{hint, HintLevel};
+pub use nishio::{NishioBudget, NishioStrategy};
+pub use pipeline::{Pipeline, Strategy};
+pub use rating::{rate, Difficulty, Rating, SEARCH_WEIGHT};
+pub use step::{Step, Technique};
+
+use step::Recorder;
 
 use super::{
     entity::{
         is_sudoku_value, new_sudoku_matrix, SudokuMatrix, SudokuMatrixValue, SudokuValueType,
-        SQUARE_OUTER_LEN,
+        SQUARE_INNER_LEN, SQUARE_OUTER_LEN,
+    },
+    rulers::{
+        each_sudoku_partition, get_sudoku_ruler_partition_map, Position, PositionPartition,
+        BOX_RULER_ID, COL_RULER_ID, RULER_COUNT,
     },
-    rulers::{each_sudoku_partition, get_sudoku_ruler_partition_map, Position, RULER_COUNT},
 };
 
-#[derive(Copy, Clone, PartialEq)]
+/// 候选值集合 can[value_id] 表示该数值是否可选
+/// 保留公开的 can 字段以兼容原有的读写 bits() 给出对应的 u16 位集 第 value_id 位对应 can[value_id]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Candidate {
     pub can: [bool; SQUARE_OUTER_LEN],
 }
@@ -30,18 +81,213 @@ impl Candidate {
             can: [false; SQUARE_OUTER_LEN],
         }
     }
+    /// 仅有这一个候选值
+    pub fn new_only(value_id: usize) -> Candidate {
+        let mut target = Candidate::new_none();
+        target.can[value_id] = true;
+        target
+    }
+    /// 超出范围的位将被忽略
+    pub fn from_bits(bits: u16) -> Candidate {
+        Candidate {
+            can: std::array::from_fn(|value_id| bits & (1 << value_id) != 0),
+        }
+    }
+    pub fn bits(&self) -> u16 {
+        self.can
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (value_id, is_candidate)| {
+                bits | (*is_candidate as u16) << value_id
+            })
+    }
 
     pub fn only(&self) -> Option<SudokuValueType> {
-        let mut count = 0;
-        let mut some = 0;
-        for (i, ele) in self.can.iter().enumerate() {
-            if *ele {
-                count += 1;
-                some = i;
-            }
+        let mut values = self.iter();
+        match (values.next(), values.next()) {
+            (Some(value_id), None) => Some(value_id + 1),
+            _ => None,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.can.iter().filter(|c| **c).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.can.contains(&true)
+    }
+
+    pub fn has(&self, value_id: usize) -> bool {
+        self.can[value_id]
+    }
+    pub fn insert(&mut self, value_id: usize) {
+        self.can[value_id] = true;
+    }
+    pub fn remove(&mut self, value_id: usize) {
+        self.can[value_id] = false;
+    }
+    pub fn set(&mut self, value_id: usize, is_candidate: bool) {
+        self.can[value_id] = is_candidate;
+    }
+
+    pub fn union(&self, other: &Candidate) -> Candidate {
+        Candidate {
+            can: std::array::from_fn(|value_id| self.can[value_id] || other.can[value_id]),
+        }
+    }
+    pub fn intersection(&self, other: &Candidate) -> Candidate {
+        Candidate {
+            can: std::array::from_fn(|value_id| self.can[value_id] && other.can[value_id]),
+        }
+    }
+    pub fn difference(&self, other: &Candidate) -> Candidate {
+        Candidate {
+            can: std::array::from_fn(|value_id| self.can[value_id] && !other.can[value_id]),
+        }
+    }
+
+    /// 由小到大依次给出可选数值的 value_id
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let can = self.can;
+        (0..SQUARE_OUTER_LEN).filter(move |value_id| can[*value_id])
+    }
+}
+
+impl From<[bool; SQUARE_OUTER_LEN]> for Candidate {
+    fn from(value: [bool; SQUARE_OUTER_LEN]) -> Self {
+        Candidate { can: value }
+    }
+}
+
+impl From<Candidate> for [bool; SQUARE_OUTER_LEN] {
+    fn from(value: Candidate) -> Self {
+        value.can
+    }
+}
+
+/// 两个不同位置处于同一分区 即互相可见
+fn is_peer(a: &Position, b: &Position) -> bool {
+    a != b
+        && get_sudoku_ruler_partition_map(a)
+            .iter()
+            .any(|partition| partition.contains(b))
+}
+
+/// 位置总数 以 row * SQUARE_OUTER_LEN + col 为序号
+const CELL_COUNT: usize = SQUARE_OUTER_LEN * SQUARE_OUTER_LEN;
+
+/// 以 1 开始的行列编号 如 r1c1
+fn cell_name((row, col): &Position) -> String {
+    format!("r{}c{}", row + 1, col + 1)
+}
+
+/// 以 1 开始的分区编号 如 row 1
+fn partition_name(ruler_id: usize, partition_id: usize) -> String {
+    let ruler = match ruler_id {
+        COL_RULER_ID => "column",
+        BOX_RULER_ID => "box",
+        _ => "row",
+    };
+    format!("{} {}", ruler, partition_id + 1)
+}
+
+/// 某一位置在该规则下所属分区的序号
+fn partition_id(ruler_id: usize, (row, col): &Position) -> usize {
+    match ruler_id {
+        COL_RULER_ID => *col,
+        BOX_RULER_ID => row / SQUARE_INNER_LEN * SQUARE_INNER_LEN + col / SQUARE_INNER_LEN,
+        _ => *row,
+    }
+}
+
+/// 分区的名称 如 box 1
+fn partition_name_of(ruler_id: usize, partition: &PositionPartition) -> String {
+    partition_name(ruler_id, partition_id(ruler_id, &partition[0]))
+}
+
+/// 位置列表 如 r1c1, r1c5
+fn cells_name(cells: &[Position]) -> String {
+    let cells: Vec<String> = cells.iter().map(cell_name).collect();
+    cells.join(", ")
+}
+
+/// 数值列表 如 1, 5
+fn values_name(value_ids: &[usize]) -> String {
+    let values: Vec<String> = value_ids
+        .iter()
+        .map(|value_id| (value_id + 1).to_string())
+        .collect();
+    values.join(", ")
+}
+
+/// 候选值集合 如 {1,5}
+fn candidate_name(can: &Candidate) -> String {
+    let values: Vec<String> = can
+        .iter()
+        .map(|value_id| (value_id + 1).to_string())
+        .collect();
+    format!("{{{}}}", values.join(","))
+}
+
+/// 全部不含假设的技巧 按难度权重由易到难 唯一解相关技巧需显式开启
+fn pattern_techniques(assume_unique: bool) -> Vec<Technique> {
+    let mut techniques: Vec<Technique> = hint::HINT_TECHNIQUES
+        .into_iter()
+        .filter(|technique| !technique.is_assumption())
+        .collect();
+    if assume_unique {
+        techniques.extend(step::UNIQUENESS_TECHNIQUES);
+        techniques.sort_by(|a, b| a.weight().total_cmp(&b.weight()));
+    }
+    techniques
+}
+
+fn cell_of(pos: &Position) -> usize {
+    pos.0 * SQUARE_OUTER_LEN + pos.1
+}
+
+fn position_of_cell(cell: usize) -> Position {
+    (cell / SQUARE_OUTER_LEN, cell % SQUARE_OUTER_LEN)
+}
+
+/// cell -> cell -> 是否互相可见
+fn peer_table() -> Vec<Vec<bool>> {
+    let mut peers = vec![vec![false; CELL_COUNT]; CELL_COUNT];
+    for (a, line) in peers.iter_mut().enumerate() {
+        for (b, is_peer_cell) in line.iter_mut().enumerate() {
+            *is_peer_cell = is_peer(&position_of_cell(a), &position_of_cell(b));
         }
-        return if count == 1 { Some(some + 1) } else { None };
     }
+    peers
+}
+
+/// 互斥组合的最大规模 即 数对、三数组、四数组
+const MAX_SUBSET_LEN: usize = 4;
+
+/// 从 0..total 中选取 size 个下标的所有组合
+fn combinations(total: usize, size: usize) -> Vec<Vec<usize>> {
+    let mut result = Vec::new();
+    let mut current = Vec::with_capacity(size);
+    fn walk(
+        start: usize,
+        total: usize,
+        size: usize,
+        current: &mut Vec<usize>,
+        result: &mut Vec<Vec<usize>>,
+    ) {
+        if current.len() == size {
+            result.push(current.clone());
+            return;
+        }
+        for i in start..total {
+            current.push(i);
+            walk(i + 1, total, size, current, result);
+            current.pop();
+        }
+    }
+    walk(0, total, size, &mut current, &mut result);
+    result
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -66,35 +312,91 @@ impl CandidateMatrix {
         finished
     }
 
-    fn set_partition_black_list(&mut self, value: &SudokuValueType, pos: &Position) {
-        let partition_list = get_sudoku_ruler_partition_map(pos);
-        for ll in partition_list.iter() {
-            for (row, col) in ll.iter() {
-                self.can_matrix[*row][*col].can[value - 1] = false;
+    /// 某一分区 某一数值 仅有两个位置可选 二者必有其一为该值
+    fn strong_links(&self, value_id: usize) -> Vec<(Position, Position)> {
+        let mut links = Vec::new();
+        each_sudoku_partition(|_, partition| {
+            let pos: Vec<Position> = partition
+                .iter()
+                .filter(|(row, col)| self.can_matrix[*row][*col].has(value_id))
+                .copied()
+                .collect();
+            if pos.len() == 2 && !links.contains(&(pos[0], pos[1])) {
+                links.push((pos[0], pos[1]));
             }
-        }
+        });
+        links
     }
 
     pub fn evolution(&mut self) {
-        let shadow = self.clone();
+        self.record_naked_single(&mut Recorder::silent());
+    }
+
+    /// 已确定值所在分区的其他位置 均不可能为该值
+    fn record_naked_single(&mut self, recorder: &mut Recorder) {
+        let shadow = *self;
         for (row, ll) in shadow.can_matrix.iter().enumerate() {
             for (col, can) in ll.iter().enumerate() {
-                if let Some(value) = can.only() {
-                    let pos = (row, col);
-                    self.set_partition_black_list(&value, &pos);
-                    self.can_matrix[row][col].can[value - 1] = true;
+                if recorder.is_done() {
+                    return;
+                }
+                let Some(value) = can.only() else {
+                    continue;
+                };
+                let pos = (row, col);
+                let partition_map = get_sudoku_ruler_partition_map(&pos);
+                // 分区内已无该候选值 无需记录
+                let is_effective = partition_map.iter().any(|partition| {
+                    partition.iter().any(|other| {
+                        *other != pos && self.can_matrix[other.0][other.1].has(value - 1)
+                    })
+                });
+                if !is_effective {
+                    continue;
                 }
+                let mut step = Step::new(Technique::NakedSingle, vec![pos], &[value - 1]);
+                for partition in partition_map.iter() {
+                    for other in partition.iter().filter(|other| **other != pos) {
+                        step.eliminate(*other, value - 1);
+                    }
+                }
+                recorder.apply(self, step, || {
+                    format!("{} can only be {}", cell_name(&pos), value)
+                });
             }
         }
     }
 
+    /// 唯一位置与区块排除 即 规则 2、3
     pub fn evolution_by_check_position(&mut self) {
+        self.record_check_position(true, true, &mut Recorder::silent());
+    }
+
+    /// 唯一位置：某一分区 某一数值 仅有一个位置可选
+    pub fn evolution_by_hidden_single(&mut self) {
+        self.record_check_position(true, false, &mut Recorder::silent());
+    }
+
+    /// 区块排除：某一分区 某一数值 所有位置均在另一分区
+    pub fn evolution_by_locked_candidates(&mut self) {
+        self.record_check_position(false, true, &mut Recorder::silent());
+    }
+
+    fn record_check_position(
+        &mut self,
+        is_hidden_single: bool,
+        is_locked: bool,
+        recorder: &mut Recorder,
+    ) {
         each_sudoku_partition(|ruler_id, partition| {
             for value_id in 0..SQUARE_OUTER_LEN {
+                if recorder.is_done() {
+                    return;
+                }
                 let mut count = 0;
                 let mut pos = [(0, 0); RULER_COUNT];
                 for (row, col) in partition.iter() {
-                    if self.can_matrix[*row][*col].can[value_id] {
+                    if self.can_matrix[*row][*col].has(value_id) {
                         count += 1;
                         if count > RULER_COUNT {
                             break;
@@ -105,126 +407,179 @@ impl CandidateMatrix {
                 let count = count;
                 let pos = pos;
                 match count {
-                    1 => {
-                        // 仅一个位置可选 值可确定
-                        let (row, col) = pos[0];
-                        self.can_matrix[row][col] = Candidate::new_none();
-                        self.can_matrix[row][col].can[value_id] = true;
+                    // 仅一个位置可选 值可确定 已确定的位置无需记录
+                    1 if is_hidden_single && self.can_matrix[pos[0].0][pos[0].1].count() > 1 => {
+                        let mut step =
+                            Step::new(Technique::HiddenSingle, partition.to_vec(), &[value_id]);
+                        step.place(pos[0], value_id);
+                        recorder.apply(self, step, || {
+                            format!(
+                                "{} is the only place for {} in {}",
+                                cell_name(&pos[0]),
+                                value_id + 1,
+                                partition_name_of(ruler_id, partition)
+                            )
+                        });
                     }
                     // 多个位置可选
-                    2 => {
+                    2 | 3 if is_locked => {
+                        let places = &pos[..count];
                         let partition_map = get_sudoku_ruler_partition_map(&pos[0]);
-                        for (current_ruler_id, partition) in partition_map.iter().enumerate() {
+                        for (current_ruler_id, other) in partition_map.iter().enumerate() {
                             if ruler_id == current_ruler_id {
                                 continue;
                             }
-                            if partition.contains(&pos[1]) {
+                            if places.iter().all(|pp| other.contains(pp)) {
                                 // 所有位置均在某一分区 可排除该分区其他位置
-                                for pp in partition.iter() {
-                                    if *pp != pos[0] && *pp != pos[1] {
-                                        self.can_matrix[pp.0][pp.1].can[value_id] = false;
-                                    }
+                                let mut step = Step::new(
+                                    Technique::LockedCandidates,
+                                    places.to_vec(),
+                                    &[value_id],
+                                );
+                                for pp in other.iter().filter(|pp| !places.contains(pp)) {
+                                    step.eliminate(*pp, value_id);
                                 }
+                                recorder.apply(self, step, || {
+                                    format!(
+                                        "{} in {} lies within {}, at {}",
+                                        value_id + 1,
+                                        partition_name_of(ruler_id, partition),
+                                        partition_name_of(current_ruler_id, other),
+                                        cells_name(places)
+                                    )
+                                });
                                 break;
                             }
                         }
                     }
-                    3 => {
-                        let partition_map = get_sudoku_ruler_partition_map(&pos[0]);
-                        for (current_ruler_id, partition) in partition_map.iter().enumerate() {
-                            if ruler_id == current_ruler_id {
-                                continue;
-                            }
-                            if partition.contains(&pos[1]) && partition.contains(&pos[2]) {
-                                // 所有位置均在某一分区 可排除该分区其他位置
-                                for pp in partition.iter() {
-                                    if *pp != pos[0] && *pp != pos[1] && *pp != pos[2] {
-                                        self.can_matrix[pp.0][pp.1].can[value_id] = false;
-                                    }
-                                }
-                                break;
-                            }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    pub fn evolution_by_value_mutex(&mut self) {
+        self.record_value_mutex(2..=MAX_SUBSET_LEN, &mut Recorder::silent());
+    }
+
+    fn record_value_mutex(&mut self, sizes: RangeInclusive<usize>, recorder: &mut Recorder) {
+        each_sudoku_partition(|ruler_id, partition| {
+            // 仅考虑未确定的位置
+            let unknown: Vec<Position> = partition
+                .iter()
+                .filter(|(row, col)| self.can_matrix[*row][*col].count() > 1)
+                .copied()
+                .collect();
+            for size in sizes.clone() {
+                for group in combinations(unknown.len(), size) {
+                    if recorder.is_done() {
+                        return;
+                    }
+                    let cells: Vec<Position> =
+                        group.iter().map(|pos_id| unknown[*pos_id]).collect();
+                    let mut union = Candidate::new_none();
+                    for (row, col) in cells.iter() {
+                        union = union.union(&self.can_matrix[*row][*col]);
+                    }
+                    if union.count() != size {
+                        continue;
+                    }
+                    let value_ids: Vec<usize> = union.iter().collect();
+                    let mut step =
+                        Step::new(Technique::NakedSubset { size }, cells.clone(), &value_ids);
+                    // 这些数值只能出现在这些位置 可排除分区其他位置
+                    for pp in partition.iter().filter(|pp| !cells.contains(pp)) {
+                        for value_id in value_ids.iter() {
+                            step.eliminate(*pp, *value_id);
                         }
                     }
-                    _ => {}
+                    recorder.apply(self, step, || {
+                        format!(
+                            "{} hold only {} in {}",
+                            cells_name(&cells),
+                            values_name(&value_ids),
+                            partition_name_of(ruler_id, partition)
+                        )
+                    });
                 }
             }
         });
     }
 
     pub fn evolution_by_position_mutex(&mut self) {
-        each_sudoku_partition(|_, partition| {
-            // value_id -> position_id -> (row, col, is_candidate)
-            let mut candidate_map = [[(0, 0, false); SQUARE_OUTER_LEN]; SQUARE_OUTER_LEN];
-            for value_id in 0..SQUARE_OUTER_LEN {
+        self.record_position_mutex(2..=MAX_SUBSET_LEN, &mut Recorder::silent());
+    }
+
+    fn record_position_mutex(&mut self, sizes: RangeInclusive<usize>, recorder: &mut Recorder) {
+        each_sudoku_partition(|ruler_id, partition| {
+            // value_id -> position_id -> is_candidate
+            let mut position_map = [[false; SQUARE_OUTER_LEN]; SQUARE_OUTER_LEN];
+            for (value_id, positions) in position_map.iter_mut().enumerate() {
                 for (pos_id, (row, col)) in partition.iter().enumerate() {
-                    candidate_map[value_id][pos_id] =
-                        (*row, *col, self.can_matrix[*row][*col].can[value_id]);
+                    positions[pos_id] = self.can_matrix[*row][*col].has(value_id);
                 }
             }
-            // 两两互斥
-            let mut double_map = [false; SQUARE_OUTER_LEN];
-            for (value_id, position_ids) in candidate_map.iter().enumerate() {
-                double_map[value_id] = position_ids.iter().filter(|p| p.2).count() == 2;
-            }
-            // 找到位置互斥的元素
-            let double_map = double_map;
-            for (value_id, position_ids) in candidate_map.iter().enumerate() {
-                if !double_map[value_id] {
-                    continue;
-                }
-                for second_value_id in (value_id + 1)..SQUARE_OUTER_LEN {
-                    if !double_map[second_value_id] {
-                        continue;
+            // 仅考虑有多个位置可选的数值
+            let unknown: Vec<usize> = (0..SQUARE_OUTER_LEN)
+                .filter(|value_id| position_map[*value_id].iter().filter(|p| **p).count() > 1)
+                .collect();
+            for size in sizes.clone() {
+                for group in combinations(unknown.len(), size) {
+                    if recorder.is_done() {
+                        return;
                     }
-                    if *position_ids == candidate_map[second_value_id] {
-                        for (row, col, is_candidate) in position_ids.iter() {
-                            if *is_candidate {
-                                self.can_matrix[*row][*col] = Candidate::new_none();
-                                self.can_matrix[*row][*col].can[value_id] = true;
-                                self.can_matrix[*row][*col].can[second_value_id] = true;
-                            }
+                    let value_ids: Vec<usize> = group.iter().map(|i| unknown[*i]).collect();
+                    let mut union = [false; SQUARE_OUTER_LEN];
+                    for value_id in value_ids.iter() {
+                        for (pos_id, is_candidate) in position_map[*value_id].iter().enumerate() {
+                            union[pos_id] |= *is_candidate;
                         }
                     }
-                }
-            }
-            // 仨仨互斥
-            let mut triple_map = [false; SQUARE_OUTER_LEN];
-            for (value_id, position_ids) in candidate_map.iter().enumerate() {
-                triple_map[value_id] = position_ids.iter().filter(|p| p.2).count() == 3;
-            }
-            // 找到位置互斥的元素
-            let triple_map = triple_map;
-            for first_value_id in 0..SQUARE_OUTER_LEN {
-                if !triple_map[first_value_id] {
-                    continue;
-                }
-                for second_value_id in (first_value_id + 1)..SQUARE_OUTER_LEN {
-                    if !triple_map[second_value_id] {
+                    if union.iter().filter(|p| **p).count() != size {
                         continue;
                     }
-                    for third_value_id in (second_value_id + 1)..SQUARE_OUTER_LEN {
-                        if !triple_map[third_value_id] {
-                            continue;
-                        }
-                        let position_ids = candidate_map[first_value_id];
-                        if position_ids == candidate_map[second_value_id]
-                            && position_ids == candidate_map[third_value_id]
-                        {
-                            for (row, col, is_candidate) in position_ids.iter() {
-                                if *is_candidate {
-                                    self.can_matrix[*row][*col] = Candidate::new_none();
-                                    self.can_matrix[*row][*col].can[first_value_id] = true;
-                                    self.can_matrix[*row][*col].can[second_value_id] = true;
-                                    self.can_matrix[*row][*col].can[third_value_id] = true;
-                                }
+                    let cells: Vec<Position> = partition
+                        .iter()
+                        .enumerate()
+                        .filter(|(pos_id, _)| union[*pos_id])
+                        .map(|(_, pos)| *pos)
+                        .collect();
+                    let mut step =
+                        Step::new(Technique::HiddenSubset { size }, cells.clone(), &value_ids);
+                    // 找到位置互斥的元素 这些位置只能是这些数值 可排除这些位置的其他候选值
+                    for pos in cells.iter() {
+                        for value_id in 0..SQUARE_OUTER_LEN {
+                            if !value_ids.contains(&value_id) {
+                                step.eliminate(*pos, value_id);
                             }
                         }
                     }
+                    recorder.apply(self, step, || {
+                        format!(
+                            "{} appear only in {} within {}",
+                            values_name(&value_ids),
+                            cells_name(&cells),
+                            partition_name_of(ruler_id, partition)
+                        )
+                    });
                 }
             }
         });
     }
+
+    /// 按难度权重依次执行一轮全部不含假设的技巧 唯一解相关技巧需显式开启
+    pub fn evolution_by_patterns(&mut self, assume_unique: bool) -> Vec<Step> {
+        pattern_techniques(assume_unique)
+            .into_iter()
+            .flat_map(|technique| self.steps_by_technique(technique))
+            .collect()
+    }
+}
+
+impl Default for CandidateMatrix {
+    fn default() -> Self {
+        CandidateMatrix::new()
+    }
 }
 
 impl From<CandidateMatrix> for SudokuMatrixValue {
@@ -249,8 +604,7 @@ impl From<SudokuMatrixValue> for CandidateMatrix {
         for (row, ll) in value.matrix.iter().enumerate() {
             for (col, value) in ll.iter().enumerate() {
                 if is_sudoku_value(*value) {
-                    target.can_matrix[row][col] = Candidate::new_none();
-                    target.can_matrix[row][col].can[value - 1] = true;
+                    target.can_matrix[row][col] = Candidate::new_only(value - 1);
                 }
             }
         }
@@ -261,10 +615,40 @@ impl From<SudokuMatrixValue> for CandidateMatrix {
 
 #[cfg(test)]
 mod tests {
-    use crate::sudoku::rulers::init;
+    use crate::sudoku::{fixture::candidate_of, rulers::init};
 
     use super::*;
 
+    #[test]
+    fn test_candidate_bitset() {
+        let a = candidate_of(&[1, 2, 5]);
+        let b = candidate_of(&[2, 5, 9]);
+        assert_eq!(a.count(), 3);
+        assert_eq!(a.union(&b), candidate_of(&[1, 2, 5, 9]));
+        assert_eq!(a.intersection(&b), candidate_of(&[2, 5]));
+        assert_eq!(a.difference(&b), candidate_of(&[1]));
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![0, 1, 4]);
+        assert_eq!(a.difference(&b).only(), Some(1));
+        assert!(a.difference(&a).is_empty());
+
+        let mut c = Candidate::new_none();
+        c.insert(8);
+        assert!(c.has(8));
+        assert_eq!(c, Candidate::new_only(8));
+        c.remove(8);
+        assert_eq!(c, Candidate::new_none());
+        assert_eq!(Candidate::from_bits(u16::MAX), Candidate::new_all());
+        assert_eq!(candidate_of(&[1, 5]).bits(), 0b10001);
+        assert_eq!(
+            candidate_of(&[1, 9]).can,
+            [true, false, false, false, false, false, false, false, true]
+        );
+        assert_eq!(
+            Candidate::from(candidate_of(&[1, 9]).can),
+            candidate_of(&[1, 9])
+        );
+    }
+
     #[test]
     fn test_into_candidate_and_evolution() {
         init();
@@ -466,6 +850,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hidden_single_apart_from_locked_candidates() {
+        init();
+
+        // 九宫格 1 中 6 仅能在第 2 行 同时第 3 行第 3 列仅剩 6 的位置
+        let sudoku = SudokuMatrixValue {
+            matrix: [
+                [0, 0, 0, 0, 0, 6, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [1, 2, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 6, 0, 0, 0, 0, 0, 0],
+            ],
+        };
+        let mut origin: CandidateMatrix = sudoku.into();
+        origin.evolution();
+
+        let mut can = origin;
+        can.evolution_by_hidden_single();
+        assert!(can.can_matrix[1][6].has(5));
+
+        let mut can = origin;
+        can.evolution_by_locked_candidates();
+        assert!(!can.can_matrix[1][6].has(5));
+        assert!(can.can_matrix[1][0].has(5) && can.can_matrix[1][1].has(5));
+        assert_eq!(
+            SudokuMatrixValue::from(can),
+            SudokuMatrixValue::from(origin)
+        );
+    }
+
     #[test]
     fn test_position_double_mutex() {
         init();
@@ -555,4 +974,111 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_value_double_mutex() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[0][1] = candidate_of(&[1, 2]);
+        can.evolution_by_value_mutex();
+        // 同行
+        assert_eq!(
+            can.can_matrix[0][8].can,
+            [false, false, true, true, true, true, true, true, true]
+        );
+        // 同九宫格
+        assert_eq!(
+            can.can_matrix[2][2].can,
+            [false, false, true, true, true, true, true, true, true]
+        );
+        // 数对本身不变
+        assert_eq!(can.can_matrix[0][0], candidate_of(&[1, 2]));
+        // 其他分区不受影响
+        assert_eq!(can.can_matrix[8][0].can, [true; 9]);
+    }
+
+    #[test]
+    fn test_value_triple_mutex() {
+        init();
+
+        // 三数组不要求每个位置都包含全部三个数值
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][4] = candidate_of(&[1, 2]);
+        can.can_matrix[3][4] = candidate_of(&[2, 3]);
+        can.can_matrix[8][4] = candidate_of(&[1, 3]);
+        can.evolution_by_value_mutex();
+        assert_eq!(
+            can.can_matrix[5][4].can,
+            [false, false, false, true, true, true, true, true, true]
+        );
+        assert_eq!(can.can_matrix[3][4], candidate_of(&[2, 3]));
+        assert_eq!(can.can_matrix[5][5].can, [true; 9]);
+    }
+
+    #[test]
+    fn test_value_quad_mutex() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[6][6] = candidate_of(&[1, 5]);
+        can.can_matrix[6][8] = candidate_of(&[5, 7, 9]);
+        can.can_matrix[8][6] = candidate_of(&[1, 7]);
+        can.can_matrix[8][8] = candidate_of(&[1, 5, 9]);
+        can.evolution_by_value_mutex();
+        assert_eq!(
+            can.can_matrix[7][7].can,
+            [false, true, true, true, false, true, false, true, false]
+        );
+        assert_eq!(can.can_matrix[6][0].can, [true; 9]);
+    }
+
+    #[test]
+    fn test_position_triple_mutex_partial() {
+        init();
+
+        // 数值 1 2 3 在第一行仅能位于前三列 且 2 只有两个位置
+        let mut can = CandidateMatrix::new();
+        for col in 3..9 {
+            can.can_matrix[0][col].remove(0);
+            can.can_matrix[0][col].remove(1);
+            can.can_matrix[0][col].remove(2);
+        }
+        can.can_matrix[0][0].remove(1);
+        can.evolution_by_position_mutex();
+        assert_eq!(can.can_matrix[0][0], candidate_of(&[1, 3]));
+        assert_eq!(can.can_matrix[0][1], candidate_of(&[1, 2, 3]));
+        assert_eq!(can.can_matrix[0][2], candidate_of(&[1, 2, 3]));
+        assert_eq!(
+            can.can_matrix[0][3].can,
+            [false, false, false, true, true, true, true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_position_quad_mutex() {
+        init();
+
+        // 数值 1 2 3 4 在第五列仅能位于 0 3 5 8 行
+        let mut can = CandidateMatrix::new();
+        for row in [1, 2, 4, 6, 7] {
+            for value_id in 0..4 {
+                can.can_matrix[row][4].remove(value_id);
+            }
+        }
+        can.can_matrix[0][4].remove(0);
+        can.can_matrix[3][4].remove(1);
+        can.can_matrix[5][4].remove(2);
+        can.can_matrix[8][4].remove(3);
+        can.evolution_by_position_mutex();
+        assert_eq!(can.can_matrix[0][4], candidate_of(&[2, 3, 4]));
+        assert_eq!(can.can_matrix[3][4], candidate_of(&[1, 3, 4]));
+        assert_eq!(can.can_matrix[5][4], candidate_of(&[1, 2, 4]));
+        assert_eq!(can.can_matrix[8][4], candidate_of(&[1, 2, 3]));
+        assert_eq!(
+            can.can_matrix[1][4].can,
+            [false, false, false, false, true, true, true, true, true]
+        );
+    }
 }
@@ -0,0 +1,439 @@
+//! 待定数组（Almost Locked Set）：某一分区内 N 个位置 候选值合计 N+1 个
+//! 去掉任意一个候选值后即成为数组 剩余候选值必然全部出现在这些位置
+//! 受限公共候选（RCC）：两个不相交的待定数组共有的数值 x 且所有含 x 的位置互相可见 因此 x 至多出现在其中一个
+//!
+//! ALS-XZ：两个待定数组以 x 相连 另一共有数值 z 必出现在其中一个
+//! -- 可将可见两者所有含 z 位置的其他位置标记 z 的黑名单
+//! 双链 ALS-XZ：两个待定数组以 x1 x2 两个数值相连 二者均成为数组
+//! -- 可将可见两者所有含 x1（x2）位置的其他位置标记黑名单 以及可见单个数组内某一数值所有位置的其他位置标记黑名单
+//! ALS-XY-Wing：A C 以 x 相连 B C 以 y 相连 A B 共有数值 z 必出现在 A 或 B
+//! -- 可将可见 A B 所有含 z 位置的其他位置标记 z 的黑名单
+//! 死亡绽放（Death Blossom）：枢纽位置的每个候选值 s 各对应一个待定数组 其中含 s 的位置均可见枢纽
+//! 无论枢纽为何值 总有一个待定数组成为数组 各待定数组共有的数值 z 必出现在其中之一
+//! -- 可将可见所有含 z 位置的其他位置标记 z 的黑名单
+
+use std::collections::HashSet;
+
+use crate::sudoku::{
+    entity::SQUARE_OUTER_LEN,
+    rulers::{each_sudoku_partition, Position},
+};
+
+use super::{
+    candidate_name, cell_name, cell_of, cells_name, combinations, peer_table, position_of_cell,
+    step::Recorder, values_name, Candidate, CandidateMatrix, Step, Technique, CELL_COUNT,
+};
+
+/// 待定数组的最大规模
+const MAX_ALS_LEN: usize = 5;
+
+/// 死亡绽放枢纽的最大候选值数量
+const MAX_STEM_LEN: usize = 3;
+
+struct AlmostLockedSet {
+    cells: Vec<usize>,
+    candidate: Candidate,
+    /// value_id -> 含该数值的位置
+    value_cells: [Vec<usize>; SQUARE_OUTER_LEN],
+}
+
+impl AlmostLockedSet {
+    fn is_disjoint(&self, other: &AlmostLockedSet) -> bool {
+        !self.cells.iter().any(|cell| other.cells.contains(cell))
+    }
+
+    /// 两个待定数组所有含该数值的位置互相可见
+    fn is_restricted(&self, other: &AlmostLockedSet, value_id: usize, peers: &[Vec<bool>]) -> bool {
+        self.candidate.has(value_id)
+            && other.candidate.has(value_id)
+            && self.value_cells[value_id]
+                .iter()
+                .all(|a| other.value_cells[value_id].iter().all(|b| peers[*a][*b]))
+    }
+
+    /// 位置与候选值 如 r1c1, r1c2 {1,2,3}
+    fn name(&self) -> String {
+        let cells: Vec<Position> = self
+            .cells
+            .iter()
+            .map(|cell| position_of_cell(*cell))
+            .collect();
+        format!("{} {}", cells_name(&cells), candidate_name(&self.candidate))
+    }
+
+    fn restricted_value_ids(&self, other: &AlmostLockedSet, peers: &[Vec<bool>]) -> Vec<usize> {
+        if !self.is_disjoint(other) {
+            return Vec::new();
+        }
+        (0..SQUARE_OUTER_LEN)
+            .filter(|value_id| self.is_restricted(other, *value_id, peers))
+            .collect()
+    }
+}
+
+impl CandidateMatrix {
+    fn almost_locked_sets(&self) -> Vec<AlmostLockedSet> {
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+        let mut result = Vec::new();
+        each_sudoku_partition(|_, partition| {
+            let unknown: Vec<Position> = partition
+                .iter()
+                .filter(|(row, col)| self.can_matrix[*row][*col].count() > 1)
+                .copied()
+                .collect();
+            for size in 1..=MAX_ALS_LEN.min(unknown.len()) {
+                for group in combinations(unknown.len(), size) {
+                    let mut candidate = Candidate::new_none();
+                    for i in group.iter() {
+                        let (row, col) = unknown[*i];
+                        candidate = candidate.union(&self.can_matrix[row][col]);
+                    }
+                    if candidate.count() != size + 1 {
+                        continue;
+                    }
+                    let mut cells: Vec<usize> =
+                        group.iter().map(|i| cell_of(&unknown[*i])).collect();
+                    cells.sort();
+                    if !seen.insert(cells.clone()) {
+                        continue;
+                    }
+                    let value_cells = std::array::from_fn(|value_id| {
+                        cells
+                            .iter()
+                            .filter(|cell| {
+                                let (row, col) = position_of_cell(**cell);
+                                self.can_matrix[row][col].has(value_id)
+                            })
+                            .copied()
+                            .collect()
+                    });
+                    result.push(AlmostLockedSet {
+                        cells,
+                        candidate,
+                        value_cells,
+                    });
+                }
+            }
+        });
+        result
+    }
+
+    pub fn evolution_by_als_xz(&mut self) {
+        self.record_als_xz(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_als_xz(&mut self, recorder: &mut Recorder) {
+        let peers = peer_table();
+        let sets = self.almost_locked_sets();
+        for (i, a) in sets.iter().enumerate() {
+            for b in sets.iter().skip(i + 1) {
+                if recorder.is_done() {
+                    return;
+                }
+                let restricted = a.restricted_value_ids(b, &peers);
+                if restricted.is_empty() {
+                    continue;
+                }
+                let link = if restricted.len() == 2 {
+                    "doubly linked"
+                } else {
+                    "linked"
+                };
+                let mut step = als_step(Technique::AlsXz, &[a, b], &restricted);
+                for x in restricted.iter() {
+                    for z in 0..SQUARE_OUTER_LEN {
+                        if z != *x && a.candidate.has(z) && b.candidate.has(z) {
+                            eliminate_seeing_sets(&mut step, z, &[a, b], &peers);
+                        }
+                    }
+                }
+                if restricted.len() == 2 {
+                    // 双链
+                    for x in restricted.iter() {
+                        eliminate_seeing_sets(&mut step, *x, &[a, b], &peers);
+                    }
+                    for set in [a, b] {
+                        for value_id in 0..SQUARE_OUTER_LEN {
+                            if set.candidate.has(value_id) && !restricted.contains(&value_id) {
+                                eliminate_seeing_sets(&mut step, value_id, &[set], &peers);
+                            }
+                        }
+                    }
+                }
+                recorder.apply(self, step, || {
+                    format!(
+                        "{} and {} {} by {}",
+                        a.name(),
+                        b.name(),
+                        link,
+                        values_name(&restricted)
+                    )
+                });
+            }
+        }
+    }
+
+    pub fn evolution_by_als_xy_wing(&mut self) {
+        self.record_als_xy_wing(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_als_xy_wing(&mut self, recorder: &mut Recorder) {
+        let peers = peer_table();
+        let sets = self.almost_locked_sets();
+        for (c_id, c) in sets.iter().enumerate() {
+            // 与 C 相连的待定数组及公共候选
+            let linked: Vec<(usize, Vec<usize>)> = sets
+                .iter()
+                .enumerate()
+                .filter(|(id, _)| *id != c_id)
+                .map(|(id, set)| (id, set.restricted_value_ids(c, &peers)))
+                .filter(|(_, restricted)| !restricted.is_empty())
+                .collect();
+            for (i, (a_id, a_restricted)) in linked.iter().enumerate() {
+                for (b_id, b_restricted) in linked.iter().skip(i + 1) {
+                    let (a, b) = (&sets[*a_id], &sets[*b_id]);
+                    if !a.is_disjoint(b) {
+                        continue;
+                    }
+                    for x in a_restricted.iter() {
+                        for y in b_restricted.iter() {
+                            if recorder.is_done() {
+                                return;
+                            }
+                            if x == y {
+                                continue;
+                            }
+                            let mut step = als_step(Technique::AlsXyWing, &[a, b, c], &[*x, *y]);
+                            for z in 0..SQUARE_OUTER_LEN {
+                                if z != *x && z != *y && a.candidate.has(z) && b.candidate.has(z) {
+                                    eliminate_seeing_sets(&mut step, z, &[a, b], &peers);
+                                }
+                            }
+                            recorder.apply(self, step, || {
+                                format!(
+                                    "{} linked to {} by {}, and {} linked to it by {}",
+                                    a.name(),
+                                    c.name(),
+                                    x + 1,
+                                    b.name(),
+                                    y + 1
+                                )
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn evolution_by_death_blossom(&mut self) {
+        self.record_death_blossom(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_death_blossom(&mut self, recorder: &mut Recorder) {
+        let peers = peer_table();
+        let sets = self.almost_locked_sets();
+        for stem in 0..CELL_COUNT {
+            let (row, col) = position_of_cell(stem);
+            let stem_can = self.can_matrix[row][col];
+            if stem_can.count() < 2 || stem_can.count() > MAX_STEM_LEN {
+                continue;
+            }
+            // 枢纽每个候选值对应的花瓣
+            let petals: Vec<Vec<&AlmostLockedSet>> = stem_can
+                .iter()
+                .map(|value_id| {
+                    sets.iter()
+                        .filter(|set| {
+                            set.candidate.has(value_id)
+                                && !set.cells.contains(&stem)
+                                && set.value_cells[value_id]
+                                    .iter()
+                                    .all(|cell| peers[stem][*cell])
+                        })
+                        .collect()
+                })
+                .collect();
+            let mut chosen: Vec<&AlmostLockedSet> = Vec::new();
+            self.death_blossom(stem, &petals, &mut chosen, &stem_can, &peers, recorder);
+        }
+    }
+
+    fn death_blossom<'a>(
+        &mut self,
+        stem: usize,
+        petals: &[Vec<&'a AlmostLockedSet>],
+        chosen: &mut Vec<&'a AlmostLockedSet>,
+        stem_can: &Candidate,
+        peers: &[Vec<bool>],
+        recorder: &mut Recorder,
+    ) {
+        if recorder.is_done() {
+            return;
+        }
+        if chosen.len() == petals.len() {
+            let stem_pos = position_of_cell(stem);
+            let mut step = als_step(
+                Technique::DeathBlossom,
+                chosen,
+                &stem_can.iter().collect::<Vec<usize>>(),
+            );
+            step.cells.insert(0, stem_pos);
+            for z in 0..SQUARE_OUTER_LEN {
+                if !stem_can.has(z) && chosen.iter().all(|set| set.candidate.has(z)) {
+                    eliminate_seeing_sets(&mut step, z, chosen, peers);
+                }
+            }
+            recorder.apply(self, step, || {
+                let petal_names: Vec<String> = stem_can
+                    .iter()
+                    .zip(chosen.iter())
+                    .map(|(value_id, set)| format!("{} -> {}", value_id + 1, set.name()))
+                    .collect();
+                format!(
+                    "stem {} {} with petals {}",
+                    cell_name(&stem_pos),
+                    candidate_name(stem_can),
+                    petal_names.join("; ")
+                )
+            });
+            return;
+        }
+        for petal in petals[chosen.len()].iter() {
+            if !chosen.iter().all(|set| set.is_disjoint(petal)) {
+                continue;
+            }
+            // 剪枝 花瓣之间必须有共同的候选值
+            let has_common = (0..SQUARE_OUTER_LEN).any(|z| {
+                !stem_can.has(z)
+                    && petal.candidate.has(z)
+                    && chosen.iter().all(|set| set.candidate.has(z))
+            });
+            if !has_common {
+                continue;
+            }
+            chosen.push(petal);
+            self.death_blossom(stem, petals, chosen, stem_can, peers, recorder);
+            chosen.pop();
+        }
+    }
+}
+
+/// 以待定数组说明的实例 位置依次为各待定数组的位置
+fn als_step(technique: Technique, sets: &[&AlmostLockedSet], value_ids: &[usize]) -> Step {
+    let cells = sets
+        .iter()
+        .flat_map(|set| set.cells.iter())
+        .map(|cell| position_of_cell(*cell))
+        .collect();
+    Step::new(technique, cells, value_ids)
+}
+
+/// 可见所有给定位置 且不在这些待定数组内的位置 均不可能为该值
+fn eliminate_seeing_sets(
+    step: &mut Step,
+    value_id: usize,
+    sets: &[&AlmostLockedSet],
+    peers: &[Vec<bool>],
+) {
+    for (cell, cell_peers) in peers.iter().enumerate() {
+        if sets.iter().any(|set| set.cells.contains(&cell)) {
+            continue;
+        }
+        let sees_all = sets.iter().all(|set| {
+            set.value_cells[value_id]
+                .iter()
+                .all(|other| cell_peers[*other])
+        });
+        if sees_all {
+            step.eliminate(position_of_cell(cell), value_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{fixture::candidate_of, rulers::init};
+
+    use super::*;
+
+    #[test]
+    fn test_als_xz() {
+        init();
+
+        // A = (1,0){1,3} (1,1){1,2} B = (4,1){2,3} 以 2 相连 z = 3
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[1][0] = candidate_of(&[1, 3]);
+        can.can_matrix[1][1] = candidate_of(&[1, 2]);
+        can.can_matrix[4][1] = candidate_of(&[2, 3]);
+        can.evolution_by_als_xz();
+        // 可见 (1,0) 与 (4,1) 的位置
+        assert!(!can.can_matrix[0][1].has(2));
+        assert!(!can.can_matrix[2][1].has(2));
+        assert!(!can.can_matrix[3][0].has(2));
+        assert!(!can.can_matrix[5][0].has(2));
+        assert!(can.can_matrix[6][1].has(2));
+        assert_eq!(can.can_matrix[1][0], candidate_of(&[1, 3]));
+    }
+
+    #[test]
+    fn test_als_step() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[1][0] = candidate_of(&[1, 3]);
+        can.can_matrix[1][1] = candidate_of(&[1, 2]);
+        can.can_matrix[4][1] = candidate_of(&[2, 3]);
+        let step = can.evolution_by_technique(Technique::AlsXz).unwrap();
+        // 依次为两个待定数组的位置
+        assert_eq!(step.cells, vec![(1, 0), (1, 1), (4, 1)]);
+        assert_eq!(step.values, vec![1]);
+        assert_eq!(step.reason, "r2c1 {1,3} and r2c2, r5c2 {1,2,3} linked by 1");
+    }
+
+    #[test]
+    fn test_doubly_linked_als_xz() {
+        init();
+
+        // A = (0,0){1,2} B = (0,4){1,3} (0,5){2,3} 以 1 2 相连
+        // 双链后 A 与 B 均成为数组 第 0 行其他位置不能为 1 2 3
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[0][4] = candidate_of(&[1, 3]);
+        can.can_matrix[0][5] = candidate_of(&[2, 3]);
+        can.evolution_by_als_xz();
+        assert_eq!(can.can_matrix[0][8], candidate_of(&[4, 5, 6, 7, 8, 9]));
+        // 可见 (0,4) (0,5) 中所有 3 的位置
+        assert!(!can.can_matrix[1][3].has(2));
+        assert!(can.can_matrix[1][3].has(0));
+    }
+
+    #[test]
+    fn test_als_xy_wing() {
+        init();
+
+        // C = (4,4){1,2} A = (4,0){1,3} B = (0,4){2,3} z = 3
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[4][4] = candidate_of(&[1, 2]);
+        can.can_matrix[4][0] = candidate_of(&[1, 3]);
+        can.can_matrix[0][4] = candidate_of(&[2, 3]);
+        can.evolution_by_als_xy_wing();
+        assert!(!can.can_matrix[0][0].has(2));
+        assert!(can.can_matrix[0][1].has(2));
+        assert!(can.can_matrix[4][4].has(0));
+    }
+
+    #[test]
+    fn test_death_blossom() {
+        init();
+
+        // 枢纽 (4,4){1,2} 花瓣 (4,0){1,7} 与 (0,4){2,7} z = 7
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[4][4] = candidate_of(&[1, 2]);
+        can.can_matrix[4][0] = candidate_of(&[1, 7]);
+        can.can_matrix[0][4] = candidate_of(&[2, 7]);
+        can.evolution_by_death_blossom();
+        assert!(!can.can_matrix[0][0].has(6));
+        assert!(can.can_matrix[0][1].has(6));
+    }
+}
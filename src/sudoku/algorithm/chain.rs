@@ -0,0 +1,361 @@
+//! 链：以候选（位置, 数值）为节点
+//! 强链：二者至少一个为真 即分区内某一数值仅有两个位置可选 或 某一位置仅有两个候选值
+//! 弱链：二者至多一个为真 即同一位置的不同候选值 或 互相可见位置的同一候选值
+//! 交替推理链（AIC）：强弱交替 首尾均为强链 则首尾至少一个为真
+//! X-Chain 仅使用同一数值的链 XY-Chain 仅使用双值格内的强链与同一数值的弱链
+//! -- 可将与首尾均有弱链的候选标记黑名单
+//! 首尾以弱链相连时即为连续环 环上每条弱链同样成为强链 以链上任意节点为首尾搜索即可覆盖
+
+use std::collections::VecDeque;
+
+use crate::sudoku::{entity::SQUARE_OUTER_LEN, rulers::Position};
+
+use super::{
+    cell_name, cell_of, peer_table, position_of_cell, step::Recorder, CandidateMatrix, Step,
+    Technique, CELL_COUNT,
+};
+
+const NODE_COUNT: usize = CELL_COUNT * SQUARE_OUTER_LEN;
+
+/// 链上允许使用的强弱链
+#[derive(Clone, Copy, PartialEq)]
+pub enum ChainKind {
+    /// 仅同一数值的链
+    X,
+    /// 双值格内的强链 与同一数值的弱链
+    XY,
+    /// 任意强弱链
+    Alternating,
+}
+
+fn node_of(pos: &Position, value_id: usize) -> usize {
+    cell_of(pos) * SQUARE_OUTER_LEN + value_id
+}
+
+fn position_of(node: usize) -> Position {
+    position_of_cell(node / SQUARE_OUTER_LEN)
+}
+
+fn value_id_of(node: usize) -> usize {
+    node % SQUARE_OUTER_LEN
+}
+
+/// 候选节点间的强弱链
+struct LinkGraph {
+    nodes: Vec<usize>,
+    strong: Vec<Vec<usize>>,
+    weak: Vec<Vec<usize>>,
+    peers: Vec<Vec<bool>>,
+}
+
+impl LinkGraph {
+    /// 二者至多一个为真
+    fn is_weak(&self, a: usize, b: usize) -> bool {
+        let (cell_a, cell_b) = (a / SQUARE_OUTER_LEN, b / SQUARE_OUTER_LEN);
+        if cell_a == cell_b {
+            a != b
+        } else {
+            value_id_of(a) == value_id_of(b) && self.peers[cell_a][cell_b]
+        }
+    }
+
+    /// 假设 start 为假 沿强弱交替的链可推出为真的所有节点
+    fn implied_on(&self, start: usize) -> Implication {
+        // node -> [为假时已访问, 为真时已访问]
+        let mut visited = vec![[false; 2]; NODE_COUNT];
+        let mut parent = vec![[None; 2]; NODE_COUNT];
+        let mut on = Vec::new();
+        let mut queue = VecDeque::from([(start, false)]);
+        visited[start][0] = true;
+        while let Some((node, is_on)) = queue.pop_front() {
+            let next_list = if is_on {
+                &self.weak[node]
+            } else {
+                &self.strong[node]
+            };
+            for next in next_list.iter() {
+                let next_state = !is_on as usize;
+                if visited[*next][next_state] {
+                    continue;
+                }
+                visited[*next][next_state] = true;
+                parent[*next][next_state] = Some(node);
+                if !is_on {
+                    on.push(*next);
+                }
+                queue.push_back((*next, !is_on));
+            }
+        }
+        Implication { on, parent }
+    }
+}
+
+/// 假设链首为假 可推出为真的节点 以及推出各节点的链
+struct Implication {
+    on: Vec<usize>,
+    /// node -> [为假时, 为真时] 链上的前一个节点
+    parent: Vec<[Option<usize>; 2]>,
+}
+
+impl Implication {
+    /// 从链首到该节点的链 首个链接为强链 之后强弱交替
+    fn chain_to(&self, end: usize) -> Vec<usize> {
+        let mut chain = vec![end];
+        let (mut node, mut is_on) = (end, true);
+        while let Some(prev) = self.parent[node][is_on as usize] {
+            chain.push(prev);
+            node = prev;
+            is_on = !is_on;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// 以 = 表示强链 - 表示弱链 如 r1c1(5)=r1c5(5)-r3c5(5)=r3c9(5)
+fn chain_name(chain: &[usize]) -> String {
+    let mut text = String::new();
+    for (i, node) in chain.iter().enumerate() {
+        if i > 0 {
+            text.push(if i % 2 == 1 { '=' } else { '-' });
+        }
+        text.push_str(&format!(
+            "{}({})",
+            cell_name(&position_of(*node)),
+            value_id_of(*node) + 1
+        ));
+    }
+    text
+}
+
+impl CandidateMatrix {
+    fn link_graph(&self, kind: ChainKind) -> LinkGraph {
+        let peers = peer_table();
+
+        let mut nodes = Vec::new();
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                for value_id in 0..SQUARE_OUTER_LEN {
+                    if self.can_matrix[row][col].has(value_id) {
+                        nodes.push(node_of(&(row, col), value_id));
+                    }
+                }
+            }
+        }
+
+        let mut strong = vec![Vec::new(); NODE_COUNT];
+        let mut weak = vec![Vec::new(); NODE_COUNT];
+        let mut add_strong = |a: usize, b: usize| {
+            if !strong[a].contains(&b) {
+                strong[a].push(b);
+                strong[b].push(a);
+            }
+        };
+
+        if kind != ChainKind::X {
+            // 双值格
+            for row in 0..SQUARE_OUTER_LEN {
+                for col in 0..SQUARE_OUTER_LEN {
+                    let can = self.can_matrix[row][col];
+                    if can.count() == 2 {
+                        let value_ids: Vec<usize> =
+                            (0..SQUARE_OUTER_LEN).filter(|i| can.has(*i)).collect();
+                        add_strong(
+                            node_of(&(row, col), value_ids[0]),
+                            node_of(&(row, col), value_ids[1]),
+                        );
+                    }
+                }
+            }
+        }
+        if kind != ChainKind::XY {
+            // 分区内仅有两个位置可选
+            for value_id in 0..SQUARE_OUTER_LEN {
+                for (a, b) in self.strong_links(value_id) {
+                    add_strong(node_of(&a, value_id), node_of(&b, value_id));
+                }
+            }
+        }
+
+        for a in nodes.iter() {
+            for b in nodes.iter() {
+                let (cell_a, cell_b) = (a / SQUARE_OUTER_LEN, b / SQUARE_OUTER_LEN);
+                let is_weak = if cell_a == cell_b {
+                    kind == ChainKind::Alternating && a != b
+                } else {
+                    value_id_of(*a) == value_id_of(*b) && peers[cell_a][cell_b]
+                };
+                if is_weak {
+                    weak[*a].push(*b);
+                }
+            }
+        }
+
+        LinkGraph {
+            nodes,
+            strong,
+            weak,
+            peers,
+        }
+    }
+
+    pub(super) fn record_inference_chain(&mut self, kind: ChainKind, recorder: &mut Recorder) {
+        let technique = match kind {
+            ChainKind::X => Technique::XChain,
+            ChainKind::XY => Technique::XYChain,
+            ChainKind::Alternating => Technique::AlternatingChain,
+        };
+        let graph = self.link_graph(kind);
+        for start in graph.nodes.iter() {
+            // 与链首有弱链的候选
+            let start_weak: Vec<usize> = graph
+                .nodes
+                .iter()
+                .filter(|node| graph.is_weak(*start, **node))
+                .copied()
+                .collect();
+            let implication = graph.implied_on(*start);
+            for end in implication.on.iter() {
+                if recorder.is_done() {
+                    return;
+                }
+                // 仅保留尚未排除的候选
+                let black_list: Vec<usize> = start_weak
+                    .iter()
+                    .filter(|node| {
+                        let (row, col) = position_of(**node);
+                        **node != *end
+                            && graph.is_weak(*end, **node)
+                            && self.can_matrix[row][col].has(value_id_of(**node))
+                    })
+                    .copied()
+                    .collect();
+                if black_list.is_empty() {
+                    continue;
+                }
+                let chain = implication.chain_to(*end);
+                let mut cells: Vec<Position> = Vec::new();
+                let mut value_ids: Vec<usize> = Vec::new();
+                for node in chain.iter() {
+                    if !cells.contains(&position_of(*node)) {
+                        cells.push(position_of(*node));
+                    }
+                    if !value_ids.contains(&value_id_of(*node)) {
+                        value_ids.push(value_id_of(*node));
+                    }
+                }
+                value_ids.sort();
+                let mut step = Step::new(technique, cells, &value_ids);
+                for node in black_list {
+                    step.eliminate(position_of(node), value_id_of(node));
+                }
+                recorder.apply(self, step, || {
+                    format!("{}, one of its ends is true", chain_name(&chain))
+                });
+            }
+        }
+    }
+
+    pub fn evolution_by_x_chain(&mut self) {
+        self.record_inference_chain(ChainKind::X, &mut Recorder::silent());
+    }
+
+    pub fn evolution_by_xy_chain(&mut self) {
+        self.record_inference_chain(ChainKind::XY, &mut Recorder::silent());
+    }
+
+    pub fn evolution_by_alternating_chain(&mut self) {
+        self.record_inference_chain(ChainKind::Alternating, &mut Recorder::silent());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        fixture::{candidate_of, column, keep_only, line},
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_x_chain() {
+        init();
+
+        // (0,0)=(0,4)-(6,4)=(6,2)-(8,1)=(4,1)
+        let mut can = CandidateMatrix::new();
+        keep_only(&mut can, 0, &line(0), &[(0, 0), (0, 4)]);
+        keep_only(&mut can, 0, &line(6), &[(6, 4), (6, 2)]);
+        keep_only(&mut can, 0, &column(1), &[(8, 1), (4, 1)]);
+        can.evolution_by_x_chain();
+        for pos in [(3, 0), (4, 0), (5, 0)] {
+            assert!(!can.can_matrix[pos.0][pos.1].has(0));
+        }
+        assert!(can.can_matrix[0][0].has(0));
+        assert!(can.can_matrix[4][1].has(0));
+        assert!(can.can_matrix[4][5].has(0));
+    }
+
+    #[test]
+    fn test_chain_step() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        keep_only(&mut can, 0, &line(0), &[(0, 0), (0, 4)]);
+        keep_only(&mut can, 0, &line(6), &[(6, 4), (6, 2)]);
+        keep_only(&mut can, 0, &column(1), &[(8, 1), (4, 1)]);
+        let step = can.evolution_by_technique(Technique::XChain).unwrap();
+        // 链上的节点 强链为 = 弱链为 -
+        assert_eq!(step.cells, vec![(0, 0), (0, 4), (6, 4), (6, 2)]);
+        assert_eq!(
+            step.reason,
+            "r1c1(1)=r1c5(1)-r7c5(1)=r7c3(1), one of its ends is true"
+        );
+        assert!(step.eliminations.contains(&((1, 2), 1)));
+    }
+
+    #[test]
+    fn test_xy_chain() {
+        init();
+
+        // (0,0){1,2} - (0,5){2,3} - (4,5){3,4} - (4,1){4,1} 首尾均为 1
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[0][5] = candidate_of(&[2, 3]);
+        can.can_matrix[4][5] = candidate_of(&[3, 4]);
+        can.can_matrix[4][1] = candidate_of(&[4, 1]);
+        let mut alternating = can;
+        can.evolution_by_xy_chain();
+        for pos in [(4, 0), (1, 1), (0, 1), (5, 0)] {
+            assert!(!can.can_matrix[pos.0][pos.1].has(0));
+        }
+        assert!(can.can_matrix[8][8].has(0));
+        assert_eq!(can.can_matrix[0][0], candidate_of(&[1, 2]));
+
+        // AIC 同样可以找到
+        alternating.evolution_by_alternating_chain();
+        for pos in [(4, 0), (1, 1), (0, 1), (5, 0)] {
+            assert!(!alternating.can_matrix[pos.0][pos.1].has(0));
+        }
+    }
+
+    #[test]
+    fn test_alternating_chain_mixed_link() {
+        init();
+
+        // (0,0)1 = (0,0)2 - (0,4)2 = (6,4)2 - (6,8)2 = (6,8)1 首尾均为 1
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[6][8] = candidate_of(&[1, 2]);
+        keep_only(&mut can, 1, &column(4), &[(0, 4), (6, 4)]);
+        let mut xy = can;
+        can.evolution_by_alternating_chain();
+        assert!(!can.can_matrix[0][8].has(0));
+        assert!(!can.can_matrix[6][0].has(0));
+        assert!(can.can_matrix[0][5].has(0));
+
+        // 缺少双值格以外的强链 XY-Chain 无法推出
+        xy.evolution_by_xy_chain();
+        assert!(xy.can_matrix[0][8].has(0));
+    }
+}
@@ -0,0 +1,278 @@
+//! 染色：某一数值的强链构成若干连通图 相邻位置必为一真一假 以两种颜色交替染色 同色位置同真同假
+//! 同色互见：同一颜色的两个位置互相可见 该颜色必为假  -- 可将该颜色所有位置标记该值的黑名单
+//! 可见双色：某一位置同时可见同一连通图的两种颜色 两种颜色必有其一为真  -- 可将该位置标记该值的黑名单
+//! 多图染色：两个连通图各有一种颜色互相可见 二者不能同时为真 则各自的另一种颜色必有其一为真
+//! -- 可将同时可见这两种颜色的位置标记该值的黑名单
+//! 若某一颜色的位置同时可见另一连通图的两种颜色 -- 可将该颜色所有位置标记该值的黑名单
+
+use std::collections::VecDeque;
+
+use crate::sudoku::{entity::SQUARE_OUTER_LEN, rulers::Position};
+
+use super::{cells_name, is_peer, step::Recorder, CandidateMatrix, Step, Technique};
+
+/// 连通图的两种颜色
+struct Cluster {
+    on: Vec<Position>,
+    off: Vec<Position>,
+}
+
+impl Cluster {
+    fn contains(&self, pos: &Position) -> bool {
+        self.on.contains(pos) || self.off.contains(pos)
+    }
+}
+
+fn sees_any(pos: &Position, positions: &[Position]) -> bool {
+    positions.iter().any(|other| is_peer(pos, other))
+}
+
+impl CandidateMatrix {
+    fn color_clusters(&self, value_id: usize) -> Vec<Cluster> {
+        let links = self.strong_links(value_id);
+        let mut colored: Vec<(Position, bool)> = Vec::new();
+        let mut clusters = Vec::new();
+        for (start, _) in links.iter() {
+            if colored.iter().any(|(pos, _)| pos == start) {
+                continue;
+            }
+            let mut cluster = Cluster {
+                on: Vec::new(),
+                off: Vec::new(),
+            };
+            let mut is_valid = true;
+            let mut queue = VecDeque::from([(*start, true)]);
+            colored.push((*start, true));
+            while let Some((pos, color)) = queue.pop_front() {
+                if color {
+                    cluster.on.push(pos);
+                } else {
+                    cluster.off.push(pos);
+                }
+                for (a, b) in links.iter() {
+                    let next = if *a == pos {
+                        *b
+                    } else if *b == pos {
+                        *a
+                    } else {
+                        continue;
+                    };
+                    match colored.iter().find(|(p, _)| *p == next) {
+                        Some((_, next_color)) => is_valid &= *next_color != color,
+                        None => {
+                            colored.push((next, !color));
+                            queue.push_back((next, !color));
+                        }
+                    }
+                }
+            }
+            // 强链构成奇数环时无法染色
+            if is_valid {
+                clusters.push(cluster);
+            }
+        }
+        clusters
+    }
+
+    pub fn evolution_by_coloring(&mut self) {
+        self.record_coloring(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_coloring(&mut self, recorder: &mut Recorder) {
+        for value_id in 0..SQUARE_OUTER_LEN {
+            let clusters = self.color_clusters(value_id);
+            let mut candidates: Vec<Position> = Vec::new();
+            for row in 0..SQUARE_OUTER_LEN {
+                for col in 0..SQUARE_OUTER_LEN {
+                    if self.can_matrix[row][col].has(value_id) {
+                        candidates.push((row, col));
+                    }
+                }
+            }
+
+            for cluster in clusters.iter() {
+                // 同色互见
+                for color in [&cluster.on, &cluster.off] {
+                    if color.iter().any(|pos| sees_any(pos, color)) {
+                        let mut step = cluster_step(value_id, &[cluster]);
+                        for pos in color.iter() {
+                            step.eliminate(*pos, value_id);
+                        }
+                        recorder.apply(self, step, || {
+                            let rule = format!("{} see each other", cells_name(color));
+                            cluster_reason(value_id, &[cluster], &rule)
+                        });
+                    }
+                }
+                // 可见双色
+                let mut step = cluster_step(value_id, &[cluster]);
+                for pos in candidates.iter() {
+                    if !cluster.contains(pos)
+                        && sees_any(pos, &cluster.on)
+                        && sees_any(pos, &cluster.off)
+                    {
+                        step.eliminate(*pos, value_id);
+                    }
+                }
+                recorder.apply(self, step, || {
+                    cluster_reason(value_id, &[cluster], "one color is true")
+                });
+                if recorder.is_done() {
+                    return;
+                }
+            }
+
+            for (i, first) in clusters.iter().enumerate() {
+                for (j, second) in clusters.iter().enumerate() {
+                    if i == j {
+                        continue;
+                    }
+                    for (first_on, first_off) in [(&first.on, &first.off), (&first.off, &first.on)]
+                    {
+                        // 可见另一连通图的两种颜色
+                        if first_on
+                            .iter()
+                            .any(|pos| sees_any(pos, &second.on) && sees_any(pos, &second.off))
+                        {
+                            let mut step = cluster_step(value_id, &[first, second]);
+                            for pos in first_on.iter() {
+                                step.eliminate(*pos, value_id);
+                            }
+                            recorder.apply(self, step, || {
+                                let rule = format!(
+                                    "{} see both colors of the other",
+                                    cells_name(first_on)
+                                );
+                                cluster_reason(value_id, &[first, second], &rule)
+                            });
+                        }
+                        if i > j {
+                            continue;
+                        }
+                        for (second_on, second_off) in
+                            [(&second.on, &second.off), (&second.off, &second.on)]
+                        {
+                            if !first_on.iter().any(|pos| sees_any(pos, second_on)) {
+                                continue;
+                            }
+                            // first_off 与 second_off 必有其一为真
+                            let mut step = cluster_step(value_id, &[first, second]);
+                            for pos in candidates.iter() {
+                                if !first.contains(pos)
+                                    && !second.contains(pos)
+                                    && sees_any(pos, first_off)
+                                    && sees_any(pos, second_off)
+                                {
+                                    step.eliminate(*pos, value_id);
+                                }
+                            }
+                            recorder.apply(self, step, || {
+                                let rule = format!(
+                                    "{} and {} cannot both be true, so one of {} and {} is",
+                                    cells_name(first_on),
+                                    cells_name(second_on),
+                                    cells_name(first_off),
+                                    cells_name(second_off)
+                                );
+                                cluster_reason(value_id, &[first, second], &rule)
+                            });
+                        }
+                        if recorder.is_done() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 以连通图的两种颜色说明的实例 位置依次为各连通图的位置
+fn cluster_step(value_id: usize, clusters: &[&Cluster]) -> Step {
+    let cells = clusters
+        .iter()
+        .flat_map(|cluster| cluster.on.iter().chain(cluster.off.iter()))
+        .copied()
+        .collect();
+    Step::new(Technique::Coloring, cells, &[value_id])
+}
+
+/// 列出各连通图的两种颜色 再给出所用的规则
+fn cluster_reason(value_id: usize, clusters: &[&Cluster], rule: &str) -> String {
+    let colors: Vec<String> = clusters
+        .iter()
+        .map(|cluster| {
+            format!(
+                "[{}] / [{}]",
+                cells_name(&cluster.on),
+                cells_name(&cluster.off)
+            )
+        })
+        .collect();
+    format!(
+        "colors of {} {}, {}",
+        value_id + 1,
+        colors.join(" and "),
+        rule
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        fixture::{column, keep_only, line, square},
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_color_trap() {
+        init();
+
+        // (0,0) - (1,2) - (1,7) - (5,7) 交替染色
+        let mut can = CandidateMatrix::new();
+        keep_only(&mut can, 0, &square(0, 0), &[(0, 0), (1, 2)]);
+        keep_only(&mut can, 0, &line(1), &[(1, 2), (1, 7)]);
+        keep_only(&mut can, 0, &column(7), &[(1, 7), (5, 7)]);
+        can.evolution_by_coloring();
+        assert!(!can.can_matrix[5][0].has(0));
+        assert!(can.can_matrix[5][1].has(0));
+        assert!(can.can_matrix[0][0].has(0));
+        assert!(can.can_matrix[5][7].has(0));
+    }
+
+    #[test]
+    fn test_color_wrap() {
+        init();
+
+        // (0,0) - (0,3) - (4,3) - (4,1) - (1,1) 其中 (0,0) 与 (1,1) 同色且同九宫格
+        let mut can = CandidateMatrix::new();
+        keep_only(&mut can, 1, &line(0), &[(0, 0), (0, 3)]);
+        keep_only(&mut can, 1, &column(3), &[(0, 3), (4, 3)]);
+        keep_only(&mut can, 1, &line(4), &[(4, 3), (4, 1)]);
+        keep_only(&mut can, 1, &column(1), &[(4, 1), (1, 1)]);
+        can.evolution_by_coloring();
+        for pos in [(0, 0), (4, 3), (1, 1)] {
+            assert!(!can.can_matrix[pos.0][pos.1].has(1));
+        }
+        for pos in [(0, 3), (4, 1)] {
+            assert!(can.can_matrix[pos.0][pos.1].has(1));
+        }
+    }
+
+    #[test]
+    fn test_multi_coloring() {
+        init();
+
+        // 连通图 (3,1) - (3,6) 与 (6,8) - (8,6) 其中 (3,6) 与 (8,6) 同列
+        let mut can = CandidateMatrix::new();
+        keep_only(&mut can, 2, &line(3), &[(3, 1), (3, 6)]);
+        keep_only(&mut can, 2, &square(6, 6), &[(6, 8), (8, 6)]);
+        can.evolution_by_coloring();
+        assert!(!can.can_matrix[6][1].has(2));
+        assert!(can.can_matrix[6][0].has(2));
+        assert!(can.can_matrix[3][1].has(2));
+        assert!(can.can_matrix[6][8].has(2));
+    }
+}
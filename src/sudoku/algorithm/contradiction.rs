@@ -0,0 +1,215 @@
+//! 矛盾检测：无候选值的位置 缺少某一数值的分区 或分区内重复的已确定值
+//! 出现矛盾说明输入有误 或推演所基于的假设不成立
+
+use std::fmt;
+
+use crate::sudoku::{
+    entity::{SudokuValueType, SQUARE_OUTER_LEN},
+    rulers::{get_sudoku_ruler_loop, Position},
+};
+
+use super::{cell_name, partition_name, CandidateMatrix, Step};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contradiction {
+    /// 同一分区内两个位置确定为同一数值
+    DuplicateValue {
+        value: SudokuValueType,
+        positions: (Position, Position),
+    },
+    /// 某一位置没有任何候选值
+    EmptyCell { position: Position },
+    /// 某一分区内没有任何位置可以填入该值
+    MissingValue {
+        value: SudokuValueType,
+        ruler_id: usize,
+        partition_id: usize,
+    },
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Contradiction::DuplicateValue {
+                value,
+                positions: (first, second),
+            } => write!(
+                f,
+                "value {} appears twice, at {} and {}",
+                value,
+                cell_name(first),
+                cell_name(second)
+            ),
+            Contradiction::EmptyCell { position } => {
+                write!(f, "{} has no candidate", cell_name(position))
+            }
+            Contradiction::MissingValue {
+                value,
+                ruler_id,
+                partition_id,
+            } => write!(
+                f,
+                "{} has no place for value {}",
+                partition_name(*ruler_id, *partition_id),
+                value
+            ),
+        }
+    }
+}
+
+impl CandidateMatrix {
+    /// 依次检查重复值 空位置 分区缺值
+    pub fn check(&self) -> Result<(), Contradiction> {
+        let ruler_loop = get_sudoku_ruler_loop();
+        for ruler in ruler_loop.iter() {
+            for partition in ruler.partitions.iter() {
+                let mut solved: [Option<Position>; SQUARE_OUTER_LEN] = [None; SQUARE_OUTER_LEN];
+                for (row, col) in partition.iter() {
+                    if let Some(value) = self.can_matrix[*row][*col].only() {
+                        if let Some(first) = solved[value - 1] {
+                            return Err(Contradiction::DuplicateValue {
+                                value,
+                                positions: (first, (*row, *col)),
+                            });
+                        }
+                        solved[value - 1] = Some((*row, *col));
+                    }
+                }
+            }
+        }
+
+        for (row, ll) in self.can_matrix.iter().enumerate() {
+            for (col, can) in ll.iter().enumerate() {
+                if can.count() == 0 {
+                    return Err(Contradiction::EmptyCell {
+                        position: (row, col),
+                    });
+                }
+            }
+        }
+
+        for (ruler_id, ruler) in ruler_loop.iter().enumerate() {
+            for (partition_id, partition) in ruler.partitions.iter().enumerate() {
+                for value_id in 0..SQUARE_OUTER_LEN {
+                    if !partition
+                        .iter()
+                        .any(|(row, col)| self.can_matrix[*row][*col].has(value_id))
+                    {
+                        return Err(Contradiction::MissingValue {
+                            value: value_id + 1,
+                            ruler_id,
+                            partition_id,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 执行一轮基础技巧 并检查结果是否矛盾
+    pub fn checked_evolution(&mut self) -> Result<(), Contradiction> {
+        self.check()?;
+        self.evolution();
+        self.evolution_by_position_mutex();
+        self.evolution_by_value_mutex();
+        self.evolution_by_check_position();
+        self.check()
+    }
+
+    /// 执行一轮全部不含假设的技巧 并检查结果是否矛盾
+    pub fn checked_evolution_by_patterns(
+        &mut self,
+        assume_unique: bool,
+    ) -> Result<Vec<Step>, Contradiction> {
+        self.check()?;
+        let steps = self.evolution_by_patterns(assume_unique);
+        self.check()?;
+        Ok(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        entity::SudokuMatrixValue,
+        fixture::{candidate_of, parse, HARD_SUDOKU},
+        rulers::{init, ROW_RULER_ID},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_duplicate_value() {
+        init();
+
+        let mut sudoku = SudokuMatrixValue::new();
+        sudoku.matrix[0][1] = 5;
+        sudoku.matrix[7][1] = 5;
+        let mut can = CandidateMatrix::from(sudoku);
+        let expected = Contradiction::DuplicateValue {
+            value: 5,
+            positions: ((0, 1), (7, 1)),
+        };
+        assert_eq!(can.check(), Err(expected));
+        assert_eq!(can.checked_evolution_by_patterns(false), Err(expected));
+    }
+
+    #[test]
+    fn test_empty_cell() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[3][4] = candidate_of(&[]);
+        assert_eq!(
+            can.check(),
+            Err(Contradiction::EmptyCell { position: (3, 4) })
+        );
+    }
+
+    #[test]
+    fn test_missing_value() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        for col in 0..SQUARE_OUTER_LEN {
+            can.can_matrix[2][col].remove(6);
+        }
+        assert_eq!(
+            can.check(),
+            Err(Contradiction::MissingValue {
+                value: 7,
+                ruler_id: ROW_RULER_ID,
+                partition_id: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_checked_evolution() {
+        init();
+
+        // (0,0) 为 1 推演后同一行的三个位置均只剩 2
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1]);
+        can.can_matrix[0][1] = candidate_of(&[1, 2]);
+        can.can_matrix[0][2] = candidate_of(&[1, 2]);
+        can.can_matrix[0][3] = candidate_of(&[1, 2]);
+        let expected = Contradiction::DuplicateValue {
+            value: 2,
+            positions: ((0, 1), (0, 2)),
+        };
+        let origin = can;
+        assert_eq!(can.checked_evolution(), Err(expected));
+        let mut can = origin;
+        assert_eq!(can.checked_evolution_by_patterns(false), Err(expected));
+
+        let origin = CandidateMatrix::from(parse(HARD_SUDOKU[2].0));
+        let mut can = origin;
+        assert_eq!(can.checked_evolution(), Ok(()));
+        assert!(can != origin);
+        let mut can = origin;
+        assert!(!can.checked_evolution_by_patterns(false).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,388 @@
+//! 鱼：某一数值 在 N 个基础分区内的位置 全部落在 N 个覆盖分区内
+//! 基础分区为行时覆盖分区为列 反之亦然 N 为 2、3、4 时分别称为 X-Wing、Swordfish、Jellyfish
+//! -- 可将覆盖分区内 基础分区以外位置的该数值标记黑名单
+//! 鱼鳍：基础分区内 覆盖分区以外的多余位置 均在同一九宫格内
+//! 若鱼鳍为真 同九宫格内位置不可能为该数值 若鱼鳍为假 则为普通的鱼
+//! -- 可将覆盖分区与鱼鳍九宫格相交 且在基础分区以外位置的该数值标记黑名单
+
+use std::ops::RangeInclusive;
+
+use crate::sudoku::{
+    entity::{new_sudoku_matrix, SudokuMatrix, SQUARE_INNER_LEN, SQUARE_OUTER_LEN},
+    rulers::{
+        get_sudoku_ruler_loop, Position, SudokuRuler, BOX_RULER_ID, COL_RULER_ID, ROW_RULER_ID,
+    },
+};
+
+use super::{
+    cells_name, combinations, partition_name, step::Recorder, CandidateMatrix, Step, Technique,
+};
+
+/// 鱼的最大规模 即 Jellyfish
+const MAX_FISH_LEN: usize = 4;
+
+/// (基础分区, 覆盖分区)
+const FISH_RULER_IDS: [(usize, usize); 2] =
+    [(ROW_RULER_ID, COL_RULER_ID), (COL_RULER_ID, ROW_RULER_ID)];
+
+/// 同一规则下的多个分区 如 rows 2, 7
+fn lines_name(ruler_id: usize, partition_ids: &[usize]) -> String {
+    let ruler = if ruler_id == ROW_RULER_ID {
+        "rows"
+    } else {
+        "columns"
+    };
+    let ids: Vec<String> = partition_ids
+        .iter()
+        .map(|id| (id + 1).to_string())
+        .collect();
+    format!("{} {}", ruler, ids.join(", "))
+}
+
+/// 每个位置在某一规则下所属分区的序号
+fn partition_ids(ruler: &SudokuRuler) -> SudokuMatrix<usize> {
+    let mut ids = new_sudoku_matrix(0);
+    for (partition_id, partition) in ruler.partitions.iter().enumerate() {
+        for (row, col) in partition.iter() {
+            ids[*row][*col] = partition_id;
+        }
+    }
+    ids
+}
+
+impl CandidateMatrix {
+    /// base_id -> cover_id -> is_candidate
+    fn fish_cover_map(
+        &self,
+        base: &SudokuRuler,
+        cover_of: &SudokuMatrix<usize>,
+        value_id: usize,
+    ) -> [[bool; SQUARE_OUTER_LEN]; SQUARE_OUTER_LEN] {
+        let mut cover_map = [[false; SQUARE_OUTER_LEN]; SQUARE_OUTER_LEN];
+        for (base_id, partition) in base.partitions.iter().enumerate() {
+            for pos in partition.iter() {
+                if self.can_matrix[pos.0][pos.1].has(value_id) {
+                    cover_map[base_id][cover_of[pos.0][pos.1]] = true;
+                }
+            }
+        }
+        cover_map
+    }
+
+    /// 基础分区内该数值的位置
+    fn fish_cells(&self, base: &SudokuRuler, base_ids: &[usize], value_id: usize) -> Vec<Position> {
+        base_ids
+            .iter()
+            .flat_map(|base_id| base.partitions[*base_id].iter())
+            .filter(|pos| self.can_matrix[pos.0][pos.1].has(value_id))
+            .copied()
+            .collect()
+    }
+
+    pub fn evolution_by_fish(&mut self) {
+        self.record_fish(2..=MAX_FISH_LEN, &mut Recorder::silent());
+    }
+
+    pub(super) fn record_fish(&mut self, sizes: RangeInclusive<usize>, recorder: &mut Recorder) {
+        let ruler_loop = get_sudoku_ruler_loop();
+        for (base_ruler_id, cover_ruler_id) in FISH_RULER_IDS {
+            let base = &ruler_loop[base_ruler_id];
+            let cover = &ruler_loop[cover_ruler_id];
+            let base_of = partition_ids(base);
+            let cover_of = partition_ids(cover);
+            for value_id in 0..SQUARE_OUTER_LEN {
+                let cover_map = self.fish_cover_map(base, &cover_of, value_id);
+                // 仅考虑有多个位置可选的基础分区
+                let lines: Vec<usize> = (0..SQUARE_OUTER_LEN)
+                    .filter(|base_id| cover_map[*base_id].iter().filter(|c| **c).count() > 1)
+                    .collect();
+                for size in sizes.clone() {
+                    for group in combinations(lines.len(), size) {
+                        if recorder.is_done() {
+                            return;
+                        }
+                        let base_ids: Vec<usize> = group.iter().map(|i| lines[*i]).collect();
+                        let mut cover_ids: Vec<usize> = Vec::new();
+                        for base_id in base_ids.iter() {
+                            for (cover_id, is_candidate) in cover_map[*base_id].iter().enumerate() {
+                                if *is_candidate && !cover_ids.contains(&cover_id) {
+                                    cover_ids.push(cover_id);
+                                }
+                            }
+                        }
+                        if cover_ids.len() != size {
+                            continue;
+                        }
+                        cover_ids.sort();
+                        let mut step = Step::new(
+                            Technique::Fish { size },
+                            self.fish_cells(base, &base_ids, value_id),
+                            &[value_id],
+                        );
+                        // 覆盖分区内 基础分区以外的位置均可排除
+                        for cover_id in cover_ids.iter() {
+                            for pos in cover.partitions[*cover_id].iter() {
+                                if !base_ids.contains(&base_of[pos.0][pos.1]) {
+                                    step.eliminate(*pos, value_id);
+                                }
+                            }
+                        }
+                        recorder.apply(self, step, || {
+                            format!(
+                                "{} in {} lies within {}",
+                                value_id + 1,
+                                lines_name(base_ruler_id, &base_ids),
+                                lines_name(cover_ruler_id, &cover_ids)
+                            )
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn evolution_by_finned_fish(&mut self) {
+        self.record_finned_fish(2..=MAX_FISH_LEN, &mut Recorder::silent());
+    }
+
+    pub(super) fn record_finned_fish(
+        &mut self,
+        sizes: RangeInclusive<usize>,
+        recorder: &mut Recorder,
+    ) {
+        let ruler_loop = get_sudoku_ruler_loop();
+        for (base_ruler_id, cover_ruler_id) in FISH_RULER_IDS {
+            let base = &ruler_loop[base_ruler_id];
+            let cover = &ruler_loop[cover_ruler_id];
+            let base_of = partition_ids(base);
+            let cover_of = partition_ids(cover);
+            for value_id in 0..SQUARE_OUTER_LEN {
+                let cover_map = self.fish_cover_map(base, &cover_of, value_id);
+                for size in sizes.clone() {
+                    // 鱼鳍最多占据一个九宫格内的位置
+                    let lines: Vec<usize> = (0..SQUARE_OUTER_LEN)
+                        .filter(|base_id| {
+                            let count = cover_map[*base_id].iter().filter(|c| **c).count();
+                            count > 0 && count <= size + SQUARE_INNER_LEN
+                        })
+                        .collect();
+                    for group in combinations(lines.len(), size) {
+                        let base_ids: Vec<usize> = group.iter().map(|i| lines[*i]).collect();
+                        let candidates = self.fish_cells(base, &base_ids, value_id);
+                        for (box_id, fin_box) in
+                            ruler_loop[BOX_RULER_ID].partitions.iter().enumerate()
+                        {
+                            if recorder.is_done() {
+                                return;
+                            }
+                            // 鱼鳍九宫格以外的位置 必须全部落在覆盖分区内
+                            let mut cover_ids: Vec<usize> = candidates
+                                .iter()
+                                .filter(|pos| !fin_box.contains(pos))
+                                .map(|pos| cover_of[pos.0][pos.1])
+                                .collect();
+                            cover_ids.sort();
+                            cover_ids.dedup();
+                            if cover_ids.len() > size {
+                                continue;
+                            }
+                            // 不足的覆盖分区 从穿过鱼鳍九宫格的分区中选取
+                            let mut box_cover_ids: Vec<usize> = fin_box
+                                .iter()
+                                .map(|pos| cover_of[pos.0][pos.1])
+                                .filter(|cover_id| !cover_ids.contains(cover_id))
+                                .collect();
+                            box_cover_ids.sort();
+                            box_cover_ids.dedup();
+                            for extra in combinations(box_cover_ids.len(), size - cover_ids.len()) {
+                                let mut cover_ids = cover_ids.clone();
+                                cover_ids.extend(extra.iter().map(|i| box_cover_ids[*i]));
+                                cover_ids.sort();
+                                let fins: Vec<Position> = candidates
+                                    .iter()
+                                    .filter(|pos| !cover_ids.contains(&cover_of[pos.0][pos.1]))
+                                    .copied()
+                                    .collect();
+                                if fins.is_empty() {
+                                    // 没有鱼鳍 即普通的鱼
+                                    continue;
+                                }
+                                let mut step = Step::new(
+                                    Technique::FinnedFish { size },
+                                    candidates.clone(),
+                                    &[value_id],
+                                );
+                                // 同时可见鱼与鱼鳍的位置
+                                for pos in fin_box.iter() {
+                                    if cover_ids.contains(&cover_of[pos.0][pos.1])
+                                        && !base_ids.contains(&base_of[pos.0][pos.1])
+                                    {
+                                        step.eliminate(*pos, value_id);
+                                    }
+                                }
+                                recorder.apply(self, step, || {
+                                    format!(
+                                        "{} in {} lies within {} except fins {} in {}",
+                                        value_id + 1,
+                                        lines_name(base_ruler_id, &base_ids),
+                                        lines_name(cover_ruler_id, &cover_ids),
+                                        cells_name(&fins),
+                                        partition_name(BOX_RULER_ID, box_id)
+                                    )
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{algorithm::Candidate, rulers::init};
+
+    use super::*;
+
+    #[test]
+    fn test_x_wing() {
+        init();
+
+        // 5 在第 1、6 行仅能位于第 2、7 列
+        let mut can = CandidateMatrix::new();
+        for row in [1, 6] {
+            for col in 0..9 {
+                if col != 2 && col != 7 {
+                    can.can_matrix[row][col].remove(4);
+                }
+            }
+        }
+        can.evolution_by_fish();
+        for row in 0..9 {
+            let expected = row == 1 || row == 6;
+            assert_eq!(can.can_matrix[row][2].has(4), expected);
+            assert_eq!(can.can_matrix[row][7].has(4), expected);
+        }
+        assert!(can.can_matrix[0][0].has(4));
+        assert_eq!(can.can_matrix[0][1], Candidate::new_all());
+    }
+
+    #[test]
+    fn test_fish_step() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        for row in [1, 6] {
+            for col in 0..9 {
+                if col != 2 && col != 7 {
+                    can.can_matrix[row][col].remove(4);
+                }
+            }
+        }
+        let step = can
+            .evolution_by_technique(Technique::Fish { size: 2 })
+            .unwrap();
+        // 基础分区内的位置
+        assert_eq!(step.cells, vec![(1, 2), (1, 7), (6, 2), (6, 7)]);
+        assert_eq!(step.values, vec![5]);
+        assert_eq!(step.reason, "5 in rows 2, 7 lies within columns 3, 8");
+        assert_eq!(step.eliminations.len(), 14);
+    }
+
+    #[test]
+    fn test_swordfish_by_column() {
+        init();
+
+        // 9 在第 0、4、8 列仅能位于 {1,4} {4,7} {1,7} 行
+        let mut can = CandidateMatrix::new();
+        let keep = [(0, [1, 4]), (4, [4, 7]), (8, [1, 7])];
+        for (col, rows) in keep {
+            for row in 0..9 {
+                if !rows.contains(&row) {
+                    can.can_matrix[row][col].remove(8);
+                }
+            }
+        }
+        can.evolution_by_fish();
+        for row in [1, 4, 7] {
+            for col in 0..9 {
+                let expected = keep
+                    .iter()
+                    .any(|(c, rows)| *c == col && rows.contains(&row));
+                assert_eq!(can.can_matrix[row][col].has(8), expected);
+            }
+        }
+        assert!(can.can_matrix[0][1].has(8));
+    }
+
+    #[test]
+    fn test_jellyfish() {
+        init();
+
+        // 3 在第 0、2、5、7 行仅能位于 1、3、6、8 列
+        let mut can = CandidateMatrix::new();
+        let keep = [(0, [1, 3]), (2, [3, 6]), (5, [6, 8]), (7, [8, 1])];
+        for (row, cols) in keep {
+            for col in 0..9 {
+                if !cols.contains(&col) {
+                    can.can_matrix[row][col].remove(2);
+                }
+            }
+        }
+        can.evolution_by_fish();
+        for col in [1, 3, 6, 8] {
+            for row in 0..9 {
+                let expected = keep
+                    .iter()
+                    .any(|(r, cols)| *r == row && cols.contains(&col));
+                assert_eq!(can.can_matrix[row][col].has(2), expected);
+            }
+        }
+        assert!(can.can_matrix[4][4].has(2));
+    }
+
+    #[test]
+    fn test_finned_x_wing() {
+        init();
+
+        // 5 在第 1 行仅能位于 2、7 列 第 7 行位于 2、7、8 列 (7, 8) 为鱼鳍
+        let mut can = CandidateMatrix::new();
+        let keep = [(1, vec![2, 7]), (7, vec![2, 7, 8])];
+        for (row, cols) in keep.iter() {
+            for col in 0..9 {
+                if !cols.contains(&col) {
+                    can.can_matrix[*row][col].remove(4);
+                }
+            }
+        }
+        can.evolution_by_finned_fish();
+        assert!(!can.can_matrix[6][7].has(4));
+        assert!(!can.can_matrix[8][7].has(4));
+        // 不可见鱼鳍
+        assert!(can.can_matrix[0][7].has(4));
+        assert!(can.can_matrix[4][2].has(4));
+        assert!(can.can_matrix[6][8].has(4));
+    }
+
+    #[test]
+    fn test_sashimi_x_wing() {
+        init();
+
+        // 5 在第 1 行仅能位于 2、7 列 第 7 行位于 2、8 列 缺少 (7, 7)
+        let mut can = CandidateMatrix::new();
+        let keep = [(1, vec![2, 7]), (7, vec![2, 8])];
+        for (row, cols) in keep.iter() {
+            for col in 0..9 {
+                if !cols.contains(&col) {
+                    can.can_matrix[*row][col].remove(4);
+                }
+            }
+        }
+        can.evolution_by_finned_fish();
+        assert!(!can.can_matrix[6][7].has(4));
+        assert!(!can.can_matrix[8][7].has(4));
+        assert!(can.can_matrix[0][7].has(4));
+        assert!(can.can_matrix[5][2].has(4));
+    }
+}
@@ -0,0 +1,367 @@
+//! 强制链：某一位置的全部候选值 或某一分区某一数值的全部位置 必有其一成立
+//! 某一候选值成立与不成立 二者亦必有其一（数字强制）
+//! 分别假设每一种情况 以基础技巧推演有限轮数 矛盾的情况直接舍弃
+//! -- 所有未矛盾情况下均被排除的候选值 可标记黑名单
+//! 强制网：分支推演无进展时 在候选值最少的位置再分支一层 取未矛盾分支的并集后继续
+
+use crate::sudoku::{
+    entity::{new_sudoku_matrix, SQUARE_OUTER_LEN},
+    rulers::{each_sudoku_partition, Position},
+};
+
+use super::{
+    candidate_name, cell_name, cells_name, partition_name_of, step::Recorder, Candidate,
+    CandidateMatrix, Step, Strategy, Technique,
+};
+
+/// 默认推演轮数
+pub const DEFAULT_FORCING_DEPTH: usize = 8;
+/// 一组假设最多包含的情况数
+const MAX_FORCING_BRANCHES: usize = 3;
+/// 强制网在分支内再分支的层数
+const FORCING_NET_DEPTH: usize = 1;
+/// 数字强制的位置最多包含的候选值数 候选值越多 不成立的情况越难推出结论
+const MAX_DIGIT_FORCING_COUNT: usize = 4;
+
+/// 以指定的推演轮数使用强制链 轮数不影响记录中的技巧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForcingChainStrategy {
+    pub depth: usize,
+}
+
+impl Default for ForcingChainStrategy {
+    fn default() -> Self {
+        ForcingChainStrategy {
+            depth: DEFAULT_FORCING_DEPTH,
+        }
+    }
+}
+
+impl Strategy for ForcingChainStrategy {
+    fn apply(&self, can: &mut CandidateMatrix) -> Vec<Step> {
+        can.steps_by(|can, recorder| can.record_forcing_chain(self.depth, recorder))
+    }
+}
+
+/// 将分支的候选值并入已有分支的并集
+fn merge(union: &mut Option<CandidateMatrix>, branch: &CandidateMatrix) {
+    let union = union.get_or_insert(CandidateMatrix {
+        can_matrix: new_sudoku_matrix(Candidate::new_none()),
+    });
+    for row in 0..SQUARE_OUTER_LEN {
+        for col in 0..SQUARE_OUTER_LEN {
+            union.can_matrix[row][col] =
+                union.can_matrix[row][col].union(&branch.can_matrix[row][col]);
+        }
+    }
+}
+
+impl CandidateMatrix {
+    /// 以基础技巧推演至多 depth 轮 出现矛盾返回 false
+    /// 尚余 nets 层时 在候选值最少的位置分支 每个分支同样推演 保留所有未矛盾分支的并集
+    fn propagate(&mut self, depth: usize, nets: usize) -> bool {
+        for _ in 0..depth {
+            let origin = *self;
+            self.evolution();
+            self.evolution_by_position_mutex();
+            self.evolution_by_value_mutex();
+            self.evolution_by_check_position();
+            if self.check().is_err() {
+                return false;
+            }
+            if origin == *self {
+                break;
+            }
+        }
+        if self.check().is_err() {
+            return false;
+        }
+        if nets == 0 {
+            return true;
+        }
+        let Some((row, col)) = self.net_cell() else {
+            return true;
+        };
+        let mut union = None;
+        for value_id in self.can_matrix[row][col].iter() {
+            let mut branch = *self;
+            branch.can_matrix[row][col] = Candidate::new_only(value_id);
+            if branch.propagate(depth, nets - 1) {
+                merge(&mut union, &branch);
+            }
+        }
+        match union {
+            Some(union) => {
+                *self = union;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 强制网分支的位置 即候选值最少的未确定位置 候选值过多时不分支
+    fn net_cell(&self) -> Option<Position> {
+        let mut chosen = None;
+        let mut min_count = MAX_FORCING_BRANCHES + 1;
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                let count = self.can_matrix[row][col].count();
+                if count > 1 && count < min_count {
+                    min_count = count;
+                    chosen = Some((row, col));
+                }
+            }
+        }
+        chosen
+    }
+
+    /// 分别假设每一种情况 所有未矛盾情况下均被排除的候选值可标记黑名单
+    /// branches 为各情况假设后的候选矩阵 cells 与 value_ids 为假设涉及的位置与数值
+    fn forcing(
+        &mut self,
+        branches: Vec<CandidateMatrix>,
+        cells: Vec<Position>,
+        value_ids: &[usize],
+        depth: usize,
+        reason: String,
+        recorder: &mut Recorder,
+    ) {
+        let mut union = None;
+        for mut branch in branches {
+            if branch.propagate(depth, FORCING_NET_DEPTH) {
+                merge(&mut union, &branch);
+            }
+        }
+        // 所有情况均矛盾 说明数独本身无解 不做处理
+        let Some(union) = union else {
+            return;
+        };
+        let mut step = Step::new(Technique::ForcingChain, cells, value_ids);
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                let excluded = self.can_matrix[row][col].difference(&union.can_matrix[row][col]);
+                for value_id in excluded.iter() {
+                    step.eliminate((row, col), value_id);
+                }
+            }
+        }
+        recorder.apply(self, step, || reason);
+    }
+
+    /// 每一种情况分别令某一位置为某一数值
+    fn forcing_on(
+        &mut self,
+        branches: &[(Position, usize)],
+        depth: usize,
+        reason: String,
+        recorder: &mut Recorder,
+    ) {
+        let assumed = branches
+            .iter()
+            .map(|((row, col), value_id)| {
+                let mut branch = *self;
+                branch.can_matrix[*row][*col] = Candidate::new_only(*value_id);
+                branch
+            })
+            .collect();
+        let mut cells: Vec<Position> = branches.iter().map(|(pos, _)| *pos).collect();
+        cells.dedup();
+        let mut value_ids: Vec<usize> = branches.iter().map(|(_, value_id)| *value_id).collect();
+        value_ids.dedup();
+        self.forcing(assumed, cells, &value_ids, depth, reason, recorder);
+    }
+
+    pub fn evolution_by_forcing_chain(&mut self, depth: usize) {
+        self.record_forcing_chain(depth, &mut Recorder::silent());
+    }
+
+    pub(super) fn record_forcing_chain(&mut self, depth: usize, recorder: &mut Recorder) {
+        // 位置强制：某一位置的全部候选值
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                if recorder.is_done() {
+                    return;
+                }
+                let can = self.can_matrix[row][col];
+                if !(2..=MAX_FORCING_BRANCHES).contains(&can.count()) {
+                    continue;
+                }
+                let branches: Vec<(Position, usize)> =
+                    can.iter().map(|value_id| ((row, col), value_id)).collect();
+                let reason = format!(
+                    "every candidate of {} {} leads to it",
+                    cell_name(&(row, col)),
+                    candidate_name(&can)
+                );
+                self.forcing_on(&branches, depth, reason, recorder);
+            }
+        }
+
+        // 分区强制：某一分区某一数值的全部位置
+        let mut groups = Vec::new();
+        each_sudoku_partition(|ruler_id, partition| {
+            for value_id in 0..SQUARE_OUTER_LEN {
+                let branches: Vec<(Position, usize)> = partition
+                    .iter()
+                    .filter(|(row, col)| self.can_matrix[*row][*col].has(value_id))
+                    .map(|pos| (*pos, value_id))
+                    .collect();
+                if (2..=MAX_FORCING_BRANCHES).contains(&branches.len()) {
+                    groups.push((partition_name_of(ruler_id, partition), branches));
+                }
+            }
+        });
+        for (name, branches) in groups.iter() {
+            if recorder.is_done() {
+                return;
+            }
+            // 分组时的快照可能已过期 仅保留仍然成立的位置
+            let branches: Vec<(Position, usize)> = branches
+                .iter()
+                .filter(|((row, col), value_id)| self.can_matrix[*row][*col].has(*value_id))
+                .copied()
+                .collect();
+            if branches.len() < 2 {
+                continue;
+            }
+            let cells: Vec<Position> = branches.iter().map(|(pos, _)| *pos).collect();
+            let reason = format!(
+                "every place of {} in {} ({}) leads to it",
+                branches[0].1 + 1,
+                name,
+                cells_name(&cells)
+            );
+            self.forcing_on(&branches, depth, reason, recorder);
+        }
+
+        self.record_digit_forcing(depth, recorder);
+    }
+
+    /// 数字强制：某一候选值成立或不成立 双值位置已由位置强制覆盖
+    fn record_digit_forcing(&mut self, depth: usize, recorder: &mut Recorder) {
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                for value_id in self.can_matrix[row][col].iter() {
+                    if recorder.is_done() {
+                        return;
+                    }
+                    let can = self.can_matrix[row][col];
+                    if !(3..=MAX_DIGIT_FORCING_COUNT).contains(&can.count()) || !can.has(value_id) {
+                        continue;
+                    }
+                    let mut on = *self;
+                    on.can_matrix[row][col] = Candidate::new_only(value_id);
+                    let mut off = *self;
+                    off.can_matrix[row][col].remove(value_id);
+                    let reason = format!(
+                        "both {} and not {} in {} lead to it",
+                        value_id + 1,
+                        value_id + 1,
+                        cell_name(&(row, col))
+                    );
+                    self.forcing(
+                        vec![on, off],
+                        vec![(row, col)],
+                        &[value_id],
+                        depth,
+                        reason,
+                        recorder,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{fixture::candidate_of, rulers::init};
+
+    use super::*;
+
+    #[test]
+    fn test_cell_forcing_chain() {
+        init();
+
+        // (4,4){1,2} 为 1 则 (4,0) 为 3 为 2 则 (0,4) 为 3
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[4][4] = candidate_of(&[1, 2]);
+        can.can_matrix[4][0] = candidate_of(&[1, 3]);
+        can.can_matrix[0][4] = candidate_of(&[2, 3]);
+        can.evolution_by_forcing_chain(DEFAULT_FORCING_DEPTH);
+        assert!(!can.can_matrix[0][0].has(2));
+        assert!(can.can_matrix[0][1].has(2));
+        assert!(can.can_matrix[1][0].has(2));
+        assert_eq!(can.can_matrix[4][4], candidate_of(&[1, 2]));
+    }
+
+    #[test]
+    fn test_forcing_chain_strategy() {
+        init();
+
+        // 推演轮数不同 记录中的技巧相同
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[4][4] = candidate_of(&[1, 2]);
+        can.can_matrix[4][0] = candidate_of(&[1, 3]);
+        can.can_matrix[0][4] = candidate_of(&[2, 3]);
+        for depth in [2, DEFAULT_FORCING_DEPTH] {
+            let mut next = can;
+            let steps = ForcingChainStrategy { depth }.apply(&mut next);
+            assert_eq!(steps[0].technique, Technique::ForcingChain);
+            assert_eq!(steps[0].cells.len(), 1);
+            assert!(!next.can_matrix[0][0].has(2));
+        }
+    }
+
+    #[test]
+    fn test_digit_forcing_chain() {
+        init();
+
+        // (4,4) 为 1 则 (4,0) 为 5 不为 1 则列 4 的 {2,3,4} 三数组使 (0,4) 为 5
+        // 四值位置不做位置强制 两种情况均排除 (0,0) 的 5
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[4][4] = candidate_of(&[1, 2, 3, 4]);
+        can.can_matrix[4][0] = candidate_of(&[1, 5]);
+        can.can_matrix[7][4] = candidate_of(&[2, 3, 4]);
+        can.can_matrix[8][4] = candidate_of(&[2, 3, 4]);
+        can.can_matrix[0][4] = candidate_of(&[2, 3, 4, 5]);
+        can.record_digit_forcing(DEFAULT_FORCING_DEPTH, &mut Recorder::silent());
+        assert!(!can.can_matrix[0][0].has(4));
+        assert!(can.can_matrix[0][1].has(4));
+        assert!(can.can_matrix[1][0].has(4));
+    }
+
+    #[test]
+    fn test_forcing_net() {
+        init();
+
+        // 基础技巧无进展 在双值位置 (0,4) 再分支 两种情况均排除 (0,0) 的 3
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[4][4] = candidate_of(&[1, 2]);
+        can.can_matrix[4][0] = candidate_of(&[1, 3]);
+        can.can_matrix[0][4] = candidate_of(&[2, 3]);
+        let mut chain = can;
+        assert!(chain.propagate(DEFAULT_FORCING_DEPTH, 0));
+        assert!(chain == can);
+        let mut net = can;
+        assert!(net.propagate(DEFAULT_FORCING_DEPTH, 1));
+        assert!(!net.can_matrix[0][0].has(2));
+        assert!(net.can_matrix[0][1].has(2));
+        assert_eq!(net.can_matrix[0][4], candidate_of(&[2, 3]));
+    }
+
+    #[test]
+    fn test_contradicted_branch() {
+        init();
+
+        // (0,0) 为 1 则 (0,1) (0,2) 同为 2 矛盾 (0,0) 只能为 2
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[0][1] = candidate_of(&[1, 2]);
+        can.can_matrix[0][2] = candidate_of(&[1, 2]);
+        assert!(can.check().is_ok());
+        let mut branch = can;
+        branch.can_matrix[0][0] = candidate_of(&[1]);
+        assert!(!branch.propagate(DEFAULT_FORCING_DEPTH, 0));
+    }
+}
@@ -0,0 +1,266 @@
+//! 提示：在副本上由易到难尝试各技巧 返回第一个可行的推演步骤 不修改原候选矩阵
+//! 提示分级给出 先是应关注的区域 再是技巧 最后是完整的推演
+
+use crate::sudoku::{
+    entity::SQUARE_OUTER_LEN,
+    rulers::{get_sudoku_ruler_loop, get_sudoku_ruler_partition_map, Position},
+};
+
+use super::{
+    cell_name, cells_name, partition_name, partition_name_of, CandidateMatrix, Step, Technique,
+};
+
+/// 由易到难的技巧顺序 与难度权重一致 不含依赖唯一解假设的技巧
+pub const HINT_TECHNIQUES: [Technique; 30] = [
+    Technique::HiddenSingle,
+    Technique::NakedSingle,
+    Technique::LockedCandidates,
+    Technique::NakedSubset { size: 2 },
+    Technique::Fish { size: 2 },
+    Technique::HiddenSubset { size: 2 },
+    Technique::NakedSubset { size: 3 },
+    Technique::Fish { size: 3 },
+    Technique::HiddenSubset { size: 3 },
+    Technique::TwoStrongLinks,
+    Technique::Wing,
+    Technique::EmptyRectangle,
+    Technique::WWing,
+    Technique::FinnedFish { size: 2 },
+    Technique::FinnedFish { size: 3 },
+    Technique::NakedSubset { size: 4 },
+    Technique::SueDeCoq,
+    Technique::Fish { size: 4 },
+    Technique::Coloring,
+    Technique::HiddenSubset { size: 4 },
+    Technique::FinnedFish { size: 4 },
+    Technique::XChain,
+    Technique::XYChain,
+    Technique::AlsXz,
+    Technique::AlternatingChain,
+    Technique::AlsXyWing,
+    Technique::DeathBlossom,
+    Technique::PatternOverlay,
+    Technique::ForcingChain,
+    Technique::Nishio,
+];
+
+/// 提示的详细程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintLevel {
+    /// 仅指出应关注的区域
+    Region,
+    /// 指出区域与技巧
+    Technique,
+    /// 完整的推演
+    Move,
+}
+
+/// 清理已确定值所在分区后 由此确定的位置与数值
+fn cleanup_placements(origin: &CandidateMatrix, can: &CandidateMatrix) -> Vec<(Position, usize)> {
+    let mut placements = Vec::new();
+    for row in 0..SQUARE_OUTER_LEN {
+        for col in 0..SQUARE_OUTER_LEN {
+            if origin.can_matrix[row][col].count() > 1 {
+                if let Some(value) = can.can_matrix[row][col].only() {
+                    placements.push(((row, col), value - 1));
+                }
+            }
+        }
+    }
+    placements
+}
+
+/// 清理产生的确定值 若同时是某一分区的唯一位置 以该分区为实例
+fn hidden_single_step(can: &CandidateMatrix, pos: &Position, value_id: usize) -> Option<Step> {
+    let (ruler_id, partition) = get_sudoku_ruler_partition_map(pos)
+        .into_iter()
+        .enumerate()
+        .find(|(_, partition)| {
+            partition
+                .iter()
+                .all(|(row, col)| (*row, *col) == *pos || !can.can_matrix[*row][*col].has(value_id))
+        })?;
+    let mut step = Step::new(Technique::HiddenSingle, partition.to_vec(), &[value_id]);
+    step.reason = format!(
+        "{} is the only place for {} in {}",
+        cell_name(pos),
+        value_id + 1,
+        partition_name_of(ruler_id, &partition)
+    );
+    step.place(*pos, value_id);
+    Some(step)
+}
+
+/// 清理产生的确定值 以该位置为实例 并排除所在分区的该值
+fn naked_single_step(can: &CandidateMatrix, pos: &Position, value_id: usize) -> Step {
+    let mut step = Step::new(Technique::NakedSingle, vec![*pos], &[value_id]);
+    step.reason = format!("{} can only be {}", cell_name(pos), value_id + 1);
+    step.place(*pos, value_id);
+    for partition in get_sudoku_ruler_partition_map(pos).iter() {
+        for (row, col) in partition.iter() {
+            if (*row, *col) != *pos && can.can_matrix[*row][*col].has(value_id) {
+                step.eliminate((*row, *col), value_id);
+            }
+        }
+    }
+    step
+}
+
+/// 下一步最简单的推演 即最简单技巧的第一个实例
+pub fn hint(can: &CandidateMatrix) -> Option<Step> {
+    next_steps(can, false).into_iter().next()
+}
+
+/// 在副本上先清理已确定值所在分区的候选值 再尝试最简单技巧的第一个实例
+/// 由清理产生的确定值按唯一位置或唯一候选值给出 较难的技巧仅在较易的技巧均无进展时尝试
+/// 假设推演每次调用代价很高 is_batch_assumption 时一次给出其全部实例
+pub(super) fn next_steps(can: &CandidateMatrix, is_batch_assumption: bool) -> Vec<Step> {
+    let origin = *can;
+    let mut can = *can;
+    can.evolution();
+    let singles = cleanup_placements(&origin, &can);
+    for technique in HINT_TECHNIQUES {
+        let found = match technique {
+            Technique::HiddenSingle => singles
+                .iter()
+                .find_map(|(pos, value_id)| hidden_single_step(&can, pos, *value_id)),
+            Technique::NakedSingle => singles
+                .first()
+                .map(|(pos, value_id)| naked_single_step(&can, pos, *value_id)),
+            _ => None,
+        };
+        let mut next = can;
+        let steps = match found {
+            Some(step) => vec![step],
+            None if is_batch_assumption && technique.is_assumption() => {
+                next.steps_by_technique(technique)
+            }
+            None => next.evolution_by_technique(technique).into_iter().collect(),
+        };
+        if !steps.is_empty() {
+            return steps;
+        }
+    }
+    Vec::new()
+}
+
+impl Step {
+    /// 包含构成该实例所有位置的分区 不存在时逐个列出位置
+    fn region_name(&self) -> String {
+        if self.cells.len() > 1 {
+            for (ruler_id, ruler) in get_sudoku_ruler_loop().iter().enumerate() {
+                for (partition_id, partition) in ruler.partitions.iter().enumerate() {
+                    if self.cells.iter().all(|pos| partition.contains(pos)) {
+                        return partition_name(ruler_id, partition_id);
+                    }
+                }
+            }
+        }
+        cells_name(&self.cells)
+    }
+
+    pub fn nudge(&self, level: HintLevel) -> String {
+        match level {
+            HintLevel::Region => format!("Look at {}.", self.region_name()),
+            HintLevel::Technique => format!(
+                "Look at {} and try {}.",
+                self.region_name(),
+                self.technique.name()
+            ),
+            HintLevel::Move => self.explanation(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        fixture::{parse, HARD_SUDOKU},
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_hint_hidden_single() {
+        init();
+
+        // 终盘去掉 (4,4) 既是唯一候选值 也是唯一位置
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        sudoku.matrix[4][4] = 0;
+        let can = CandidateMatrix::from(sudoku);
+        let origin = can;
+        let step = hint(&can).unwrap();
+        assert!(can == origin);
+        assert_eq!(step.technique, Technique::HiddenSingle);
+        assert_eq!(step.cells.len(), 9);
+        assert_eq!(step.placements, vec![((4, 4), 8)]);
+        assert!(step.eliminations.is_empty());
+        assert_eq!(step.nudge(HintLevel::Region), "Look at row 5.");
+        assert_eq!(
+            step.nudge(HintLevel::Technique),
+            "Look at row 5 and try Hidden Single."
+        );
+        assert!(step.nudge(HintLevel::Move).ends_with(" place r5c5=8;"));
+    }
+
+    #[test]
+    fn test_hint_naked_single() {
+        init();
+
+        // 仅 (8,3) 为唯一候选值 且各分区均无唯一位置
+        let sudoku = parse(
+            "1.......35...29........35...........9..5.67...2.7.41.5...........19..8678...61354",
+        );
+        let step = hint(&CandidateMatrix::from(sudoku)).unwrap();
+        assert_eq!(step.technique, Technique::NakedSingle);
+        assert_eq!(step.cells, vec![(8, 3)]);
+        assert_eq!(step.placements, vec![((8, 3), 2)]);
+        assert!(step.eliminations.iter().all(|(_, value)| *value == 2));
+        assert_eq!(step.nudge(HintLevel::Region), "Look at r9c4.");
+    }
+
+    #[test]
+    fn test_hint_region_of_pattern() {
+        init();
+
+        // 5 在第 1 行仅能位于第 1 个九宫格 排除的是该九宫格另两行的 5
+        let mut can = CandidateMatrix::new();
+        for col in 3..9 {
+            can.can_matrix[0][col].remove(4);
+        }
+        let step = hint(&can).unwrap();
+        assert_eq!(step.technique, Technique::LockedCandidates);
+        assert_eq!(step.cells, vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(step.eliminations.len(), 6);
+        assert!(step.eliminations.iter().all(|((row, _), _)| *row != 0));
+        assert_eq!(step.nudge(HintLevel::Region), "Look at row 1.");
+    }
+
+    #[test]
+    fn test_hint_follows_solution() {
+        init();
+
+        let (sudoku, solution) = HARD_SUDOKU[2];
+        let solution = parse(solution);
+        let mut can = CandidateMatrix::from(parse(sudoku));
+        for _ in 0..3 {
+            let step = hint(&can).unwrap();
+            for ((row, col), value) in step.placements.iter() {
+                assert_eq!(solution.matrix[*row][*col], *value);
+            }
+            for ((row, col), value) in step.eliminations.iter() {
+                assert_ne!(solution.matrix[*row][*col], *value);
+            }
+            can.apply_step(&step);
+        }
+    }
+
+    #[test]
+    fn test_no_hint() {
+        init();
+
+        let can = CandidateMatrix::from(parse(HARD_SUDOKU[0].1));
+        assert_eq!(hint(&can), None);
+    }
+}
@@ -0,0 +1,299 @@
+//! Nishio：假设某一候选值成立 在副本上以基础技巧推演
+//! 若出现无候选值的位置 或分区缺少某一数值  -- 该候选值可标记黑名单
+//! 推演中可嵌套假设 嵌套层数与推演轮数均有上限 超出上限时不做结论
+//! 轮数上限与耗时无关 同一题目每次得到相同的结论 另可设时限 到时即停止推演
+
+use std::time::{Duration, Instant};
+
+use crate::sudoku::entity::SQUARE_OUTER_LEN;
+
+use super::{
+    cell_name,
+    step::{Recorder, UNIQUENESS_TECHNIQUES},
+    Candidate, CandidateMatrix, Step, Strategy, Technique,
+};
+
+/// 假设推演的上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NishioBudget {
+    /// 嵌套假设的层数 为 1 时推演中不再假设
+    pub depth: usize,
+    /// 单次调用中 所有假设分支推演的总轮数
+    pub passes: usize,
+    /// 单次调用的时限 为 None 时不限时 设置后结论可能随机器快慢而不同
+    pub deadline: Option<Duration>,
+}
+
+impl NishioBudget {
+    pub const DEFAULT: NishioBudget = NishioBudget {
+        depth: 1,
+        passes: 300,
+        deadline: None,
+    };
+}
+
+impl Default for NishioBudget {
+    fn default() -> Self {
+        NishioBudget::DEFAULT
+    }
+}
+
+/// 以指定的上限使用 Nishio 上限不影响记录中的技巧
+/// assume_unique 时分支内的推演也使用唯一解相关技巧
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NishioStrategy {
+    pub budget: NishioBudget,
+    pub assume_unique: bool,
+}
+
+impl Strategy for NishioStrategy {
+    fn apply(&self, can: &mut CandidateMatrix) -> Vec<Step> {
+        can.steps_by(|can, recorder| can.record_nishio(self.budget, self.assume_unique, recorder))
+    }
+}
+
+/// 单次调用剩余的推演轮数与截止时刻
+struct Allowance {
+    passes: usize,
+    deadline: Option<Instant>,
+}
+
+impl Allowance {
+    fn is_spent(&self) -> bool {
+        self.passes == 0
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+impl CandidateMatrix {
+    pub fn evolution_by_nishio(&mut self, budget: NishioBudget, assume_unique: bool) {
+        self.record_nishio(budget, assume_unique, &mut Recorder::silent());
+    }
+
+    pub(super) fn record_nishio(
+        &mut self,
+        budget: NishioBudget,
+        assume_unique: bool,
+        recorder: &mut Recorder,
+    ) {
+        let mut allowance = Allowance {
+            passes: budget.passes,
+            deadline: budget.deadline.map(|limit| Instant::now() + limit),
+        };
+        self.nishio(budget, &mut allowance, assume_unique, recorder);
+    }
+
+    /// budget.depth 为剩余的嵌套层数
+    fn nishio(
+        &mut self,
+        budget: NishioBudget,
+        allowance: &mut Allowance,
+        assume_unique: bool,
+        recorder: &mut Recorder,
+    ) {
+        if budget.depth == 0 {
+            return;
+        }
+        let inner = NishioBudget {
+            depth: budget.depth - 1,
+            ..budget
+        };
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                if self.can_matrix[row][col].count() < 2 {
+                    continue;
+                }
+                for value_id in 0..SQUARE_OUTER_LEN {
+                    if !self.can_matrix[row][col].has(value_id) {
+                        continue;
+                    }
+                    if allowance.is_spent() || recorder.is_done() {
+                        return;
+                    }
+                    let mut branch = *self;
+                    branch.can_matrix[row][col] = Candidate::new_only(value_id);
+                    if branch.is_refuted(inner, allowance, assume_unique) {
+                        let mut step = Step::new(Technique::Nishio, vec![(row, col)], &[value_id]);
+                        step.eliminate((row, col), value_id);
+                        recorder.apply(self, step, || {
+                            format!(
+                                "assuming {}={} leads to a contradiction",
+                                cell_name(&(row, col)),
+                                value_id + 1
+                            )
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// 推演至无进展 出现矛盾返回 true 轮数用尽或到时视为未矛盾
+    fn is_refuted(
+        &mut self,
+        budget: NishioBudget,
+        allowance: &mut Allowance,
+        assume_unique: bool,
+    ) -> bool {
+        loop {
+            if self.check().is_err() {
+                return true;
+            }
+            if allowance.is_spent() {
+                return false;
+            }
+            allowance.passes -= 1;
+            let origin = *self;
+            self.evolution();
+            self.evolution_by_position_mutex();
+            self.evolution_by_value_mutex();
+            self.evolution_by_check_position();
+            // 分支内的推演无需记录
+            let mut recorder = Recorder::silent();
+            if assume_unique {
+                for technique in UNIQUENESS_TECHNIQUES {
+                    self.record(technique, &mut recorder);
+                }
+            }
+            if origin == *self {
+                self.nishio(budget, allowance, assume_unique, &mut recorder);
+            }
+            if origin == *self {
+                return self.check().is_err();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        fixture::{candidate_of, parse, HARD_SUDOKU},
+        rulers::init,
+    };
+
+    use super::*;
+
+    /// 终盘中 (0,0) (0,1) 两个位置改为 {1,6}
+    fn almost_solved() -> CandidateMatrix {
+        let mut can = CandidateMatrix::from(parse(HARD_SUDOKU[0].1));
+        can.can_matrix[0][0] = candidate_of(&[1, 6]);
+        can.can_matrix[0][1] = candidate_of(&[1, 6]);
+        can
+    }
+
+    #[test]
+    fn test_nishio() {
+        init();
+
+        // (0,0) 为 6 则与第 0 列已有的 6 矛盾 (0,1) 为 1 同理
+        let mut can = almost_solved();
+        let budget = NishioBudget {
+            depth: 1,
+            passes: usize::MAX,
+            deadline: None,
+        };
+        can.evolution_by_nishio(budget, false);
+        assert_eq!(can.can_matrix[0][0], candidate_of(&[1]));
+        assert_eq!(can.can_matrix[0][1], candidate_of(&[6]));
+    }
+
+    #[test]
+    fn test_nishio_strategy() {
+        init();
+
+        // 上限不同 得到的步骤相同
+        let strategy = NishioStrategy {
+            budget: NishioBudget {
+                depth: 2,
+                passes: usize::MAX,
+                deadline: Some(Duration::from_secs(60)),
+            },
+            assume_unique: true,
+        };
+        let mut can = almost_solved();
+        let steps = strategy.apply(&mut can);
+        let mut other = almost_solved();
+        assert_eq!(NishioStrategy::default().apply(&mut other), steps);
+        assert_eq!(steps[0].technique, Technique::Nishio);
+        assert!(can == other);
+    }
+
+    #[test]
+    fn test_nishio_without_budget() {
+        init();
+
+        let mut can = almost_solved();
+        let origin = can;
+        let budget = NishioBudget {
+            depth: 0,
+            passes: usize::MAX,
+            deadline: None,
+        };
+        can.evolution_by_nishio(budget, false);
+        assert!(can == origin);
+        let budget = NishioBudget {
+            depth: 1,
+            passes: 0,
+            deadline: None,
+        };
+        can.evolution_by_nishio(budget, false);
+        assert!(can == origin);
+    }
+
+    #[test]
+    fn test_nishio_deterministic() {
+        init();
+
+        // 同样的上限 无论耗时如何 结论相同
+        let budget = NishioBudget {
+            depth: 1,
+            passes: 5,
+            deadline: None,
+        };
+        let origin = CandidateMatrix::from(parse(HARD_SUDOKU[0].0));
+        let mut first = origin;
+        first.evolution_by_nishio(budget, false);
+        let mut second = origin;
+        second.evolution_by_nishio(budget, false);
+        assert!(first == second);
+    }
+
+    #[test]
+    fn test_nishio_deadline() {
+        init();
+
+        // 轮数不限 但时限已到 不做任何结论
+        let mut can = almost_solved();
+        let origin = can;
+        let budget = NishioBudget {
+            depth: 1,
+            passes: usize::MAX,
+            deadline: Some(Duration::ZERO),
+        };
+        can.evolution_by_nishio(budget, false);
+        assert!(can == origin);
+
+        // 时限充裕时与不限时结论相同
+        let budget = NishioBudget {
+            deadline: Some(Duration::from_secs(60)),
+            ..budget
+        };
+        can.evolution_by_nishio(budget, false);
+        assert_eq!(can.can_matrix[0][0], candidate_of(&[1]));
+        assert_eq!(can.can_matrix[0][1], candidate_of(&[6]));
+
+        // 难题上嵌套假设且轮数不限 时限已到同样不做任何结论
+        let budget = NishioBudget {
+            depth: 2,
+            passes: usize::MAX,
+            deadline: Some(Duration::ZERO),
+        };
+        let mut can = CandidateMatrix::from(parse(HARD_SUDOKU[0].0));
+        let origin = can;
+        can.evolution_by_nishio(budget, false);
+        assert!(can == origin);
+    }
+}
@@ -0,0 +1,190 @@
+//! 模板法：某一数值在终盘中的位置 每行每列每个九宫格各一个 共 46656 种模板
+//! 排除与候选值冲突 或未覆盖已确定位置的模板
+//! -- 不在任何剩余模板中的位置可标记黑名单 在所有剩余模板中的位置可确定值
+
+use std::sync::OnceLock;
+
+use crate::sudoku::{
+    entity::{SQUARE_INNER_LEN, SQUARE_OUTER_LEN},
+    rulers::Position,
+};
+
+use super::{step::Recorder, CandidateMatrix, Step, Technique};
+
+/// 每个位置对应一个比特 共 81 位
+type PositionMask = u128;
+
+fn bit_of(row: usize, col: usize) -> PositionMask {
+    1 << (row * SQUARE_OUTER_LEN + col)
+}
+
+/// 逐行选取列 列不重复 且同一横向三个九宫格各选一次
+fn templates() -> &'static Vec<PositionMask> {
+    static TEMPLATES: OnceLock<Vec<PositionMask>> = OnceLock::new();
+    TEMPLATES.get_or_init(|| {
+        fn walk(
+            row: usize,
+            used_cols: [bool; SQUARE_OUTER_LEN],
+            used_boxes: [bool; SQUARE_INNER_LEN],
+            mask: PositionMask,
+            result: &mut Vec<PositionMask>,
+        ) {
+            if row == SQUARE_OUTER_LEN {
+                result.push(mask);
+                return;
+            }
+            // 进入新的横向九宫格时重置
+            let used_boxes = if row.is_multiple_of(SQUARE_INNER_LEN) {
+                [false; SQUARE_INNER_LEN]
+            } else {
+                used_boxes
+            };
+            for col in 0..SQUARE_OUTER_LEN {
+                let box_id = col / SQUARE_INNER_LEN;
+                if used_cols[col] || used_boxes[box_id] {
+                    continue;
+                }
+                let mut next_cols = used_cols;
+                next_cols[col] = true;
+                let mut next_boxes = used_boxes;
+                next_boxes[box_id] = true;
+                walk(
+                    row + 1,
+                    next_cols,
+                    next_boxes,
+                    mask | bit_of(row, col),
+                    result,
+                );
+            }
+        }
+
+        let mut result = Vec::new();
+        walk(
+            0,
+            [false; SQUARE_OUTER_LEN],
+            [false; SQUARE_INNER_LEN],
+            0,
+            &mut result,
+        );
+        result
+    })
+}
+
+impl CandidateMatrix {
+    pub fn evolution_by_pattern_overlay(&mut self) {
+        self.record_pattern_overlay(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_pattern_overlay(&mut self, recorder: &mut Recorder) {
+        for value_id in 0..SQUARE_OUTER_LEN {
+            if recorder.is_done() {
+                return;
+            }
+            let mut candidate_mask: PositionMask = 0;
+            let mut solved_mask: PositionMask = 0;
+            let mut cells: Vec<Position> = Vec::new();
+            for (row, ll) in self.can_matrix.iter().enumerate() {
+                for (col, can) in ll.iter().enumerate() {
+                    if can.has(value_id) {
+                        candidate_mask |= bit_of(row, col);
+                        cells.push((row, col));
+                        if can.count() == 1 {
+                            solved_mask |= bit_of(row, col);
+                        }
+                    }
+                }
+            }
+
+            let mut union: PositionMask = 0;
+            let mut intersection: PositionMask = !0;
+            let mut matched = 0;
+            for template in templates().iter() {
+                if template & !candidate_mask != 0 || solved_mask & !template != 0 {
+                    continue;
+                }
+                matched += 1;
+                union |= template;
+                intersection &= template;
+            }
+            // 无可用模板 说明数独本身无解 不做处理
+            if matched == 0 {
+                continue;
+            }
+
+            let mut step = Step::new(Technique::PatternOverlay, cells, &[value_id]);
+            for row in 0..SQUARE_OUTER_LEN {
+                for col in 0..SQUARE_OUTER_LEN {
+                    if union & bit_of(row, col) == 0 {
+                        step.eliminate((row, col), value_id);
+                    } else if intersection & bit_of(row, col) != 0 {
+                        step.place((row, col), value_id);
+                    }
+                }
+            }
+            recorder.apply(self, step, || {
+                format!(
+                    "{} of the templates of {} fit the candidates",
+                    matched,
+                    value_id + 1
+                )
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        algorithm::Candidate,
+        fixture::{candidate_of, parse, HARD_SUDOKU},
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_templates() {
+        assert_eq!(templates().len(), 46656);
+        for template in templates().iter() {
+            assert_eq!(template.count_ones() as usize, SQUARE_OUTER_LEN);
+        }
+    }
+
+    #[test]
+    fn test_pattern_overlay() {
+        // 数值 1 第 0 行仅在 (0,0) (0,1) 第 1 行仅在 (1,0) (1,1) (1,3)
+        let mut can = CandidateMatrix::new();
+        for col in 0..SQUARE_OUTER_LEN {
+            can.can_matrix[0][col].set(0, col < 2);
+            can.can_matrix[1][col].set(0, col < 2 || col == 3);
+        }
+        can.evolution_by_pattern_overlay();
+        // 第 1 行只能在第二个九宫格
+        assert_eq!(can.can_matrix[1][3], candidate_of(&[1]));
+        assert!(!can.can_matrix[1][0].has(0));
+        // 第 2 行只能在第三个九宫格
+        for col in 0..6 {
+            assert!(!can.can_matrix[2][col].has(0));
+        }
+        assert!(can.can_matrix[2][6].has(0));
+        assert_eq!(can.can_matrix[0][0], Candidate::new_all());
+    }
+
+    #[test]
+    fn test_pattern_overlay_place() {
+        init();
+
+        // 终盘中所有 1 的位置改为全部候选 仅剩一种模板
+        let solution = parse(HARD_SUDOKU[0].1);
+        let mut can = CandidateMatrix::from(solution);
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                if solution.matrix[row][col] == 1 {
+                    can.can_matrix[row][col] = Candidate::new_all();
+                }
+            }
+        }
+        can.evolution_by_pattern_overlay();
+        assert!(can == CandidateMatrix::from(solution));
+    }
+}
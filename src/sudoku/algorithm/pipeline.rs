@@ -0,0 +1,207 @@
+//! 推演流水线：由调用方决定启用哪些技巧及其顺序
+//! 每当某一技巧产生变化 都从第一个技巧重新开始 直至所有技巧均无进展
+
+use super::{
+    pattern_techniques, CandidateMatrix, Contradiction, ForcingChainStrategy, NishioBudget,
+    NishioStrategy, Step, Technique,
+};
+
+/// 一种推演策略 执行一轮 依次返回每个实例的记录 无变化时为空
+pub trait Strategy {
+    fn apply(&self, can: &mut CandidateMatrix) -> Vec<Step>;
+}
+
+impl Strategy for Technique {
+    fn apply(&self, can: &mut CandidateMatrix) -> Vec<Step> {
+        can.steps_by_technique(*self)
+    }
+}
+
+#[derive(Default)]
+pub struct Pipeline {
+    strategies: Vec<Box<dyn Strategy>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// 按给定顺序使用这些技巧
+    pub fn from_techniques(techniques: &[Technique]) -> Pipeline {
+        techniques
+            .iter()
+            .fold(Pipeline::new(), |pipeline, technique| {
+                pipeline.with(*technique)
+            })
+    }
+
+    /// 仅使用唯一位置与唯一候选值
+    pub fn singles() -> Pipeline {
+        Pipeline::from_techniques(&[Technique::HiddenSingle, Technique::NakedSingle])
+    }
+
+    /// 不含假设的技巧由易到难 唯一解相关技巧需显式开启
+    pub fn standard(assume_unique: bool) -> Pipeline {
+        Pipeline::by_weight(assume_unique, false)
+    }
+
+    /// 在标准技巧之后 再以默认上限使用强制链与 Nishio 等假设推演 耗时可能很长
+    /// 需要其他上限时 在标准流水线之后追加 ForcingChainStrategy 与 NishioStrategy
+    pub fn exhaustive(assume_unique: bool) -> Pipeline {
+        Pipeline::by_weight(assume_unique, true)
+    }
+
+    fn by_weight(assume_unique: bool, with_assumption: bool) -> Pipeline {
+        let pipeline = Pipeline::from_techniques(&pattern_techniques(assume_unique));
+        if !with_assumption {
+            return pipeline;
+        }
+        pipeline
+            .with(ForcingChainStrategy::default())
+            .with(NishioStrategy {
+                budget: NishioBudget::DEFAULT,
+                assume_unique,
+            })
+    }
+
+    /// 在末尾追加一种策略
+    pub fn with<S: Strategy + 'static>(mut self, strategy: S) -> Pipeline {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    /// 推演至无进展 每个实例都通知调用方 每轮技巧之后检查矛盾
+    pub fn run_with<F>(
+        &self,
+        can: &mut CandidateMatrix,
+        mut on_step: F,
+    ) -> Result<Vec<Step>, Contradiction>
+    where
+        F: FnMut(&Step, &CandidateMatrix),
+    {
+        can.check()?;
+        let mut steps = Vec::new();
+        'restart: loop {
+            for strategy in self.strategies.iter() {
+                let origin = *can;
+                let found = strategy.apply(can);
+                if found.is_empty() {
+                    continue;
+                }
+                // 逐个重放 调用方看到的是每个实例执行后的候选值
+                let mut replay = origin;
+                for step in found {
+                    replay.apply_step(&step);
+                    on_step(&step, &replay);
+                    steps.push(step);
+                }
+                can.check()?;
+                continue 'restart;
+            }
+            return Ok(steps);
+        }
+    }
+
+    pub fn run(&self, can: &mut CandidateMatrix) -> Result<Vec<Step>, Contradiction> {
+        self.run_with(can, |_, _| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        algorithm::{hint::HINT_TECHNIQUES, Contradiction},
+        fixture::{assert_keeps_solution, parse, HARD_SUDOKU},
+        rulers::init,
+    };
+
+    use super::*;
+
+    /// 仅清理已确定值的候选值
+    struct Cleanup;
+
+    impl Strategy for Cleanup {
+        fn apply(&self, can: &mut CandidateMatrix) -> Vec<Step> {
+            can.steps_by_technique(Technique::NakedSingle)
+        }
+    }
+
+    #[test]
+    fn test_singles_pipeline() {
+        init();
+
+        let (sudoku, solution) = HARD_SUDOKU[2];
+        let mut can = CandidateMatrix::from(parse(sudoku));
+        let steps = Pipeline::singles().run(&mut can).unwrap();
+        assert!(can.finished());
+        assert_keeps_solution(&can, solution);
+        assert!(steps.iter().all(|step| matches!(
+            step.technique,
+            Technique::HiddenSingle | Technique::NakedSingle
+        )));
+    }
+
+    #[test]
+    fn test_custom_pipeline() {
+        init();
+
+        let (sudoku, solution) = HARD_SUDOKU[0];
+        let mut can = CandidateMatrix::from(parse(sudoku));
+        let mut count = 0;
+        let steps = Pipeline::new()
+            .with(Cleanup)
+            .with(Technique::Fish { size: 2 })
+            .run_with(&mut can, |_, _| count += 1)
+            .unwrap();
+        assert_eq!(steps.len(), count);
+        assert!(!can.finished());
+        assert_keeps_solution(&can, solution);
+        assert!(steps.iter().all(|step| matches!(
+            step.technique,
+            Technique::NakedSingle | Technique::Fish { size: 2 }
+        )));
+    }
+
+    #[test]
+    fn test_pattern_order_by_weight() {
+        // 单步推演 流水线 提示与评级共用同一个由易到难的顺序
+        for assume_unique in [false, true] {
+            let techniques = pattern_techniques(assume_unique);
+            assert_eq!(techniques[0], Technique::HiddenSingle);
+            for pair in techniques.windows(2) {
+                assert!(pair[0].weight() < pair[1].weight());
+            }
+        }
+    }
+
+    #[test]
+    fn test_standard_pipeline() {
+        init();
+
+        // 标准流水线不含强制链与 Nishio
+        let standard = Pipeline::standard(true);
+        assert_eq!(standard.strategies.len(), HINT_TECHNIQUES.len());
+        let exhaustive = Pipeline::exhaustive(true);
+        assert_eq!(exhaustive.strategies.len(), HINT_TECHNIQUES.len() + 2);
+        let (sudoku, solution) = HARD_SUDOKU[2];
+        let mut can = CandidateMatrix::from(parse(sudoku));
+        Pipeline::standard(false).run(&mut can).unwrap();
+        assert!(can.finished());
+        assert_keeps_solution(&can, solution);
+    }
+
+    #[test]
+    fn test_pipeline_contradiction() {
+        init();
+
+        let mut sudoku = parse(HARD_SUDOKU[2].0);
+        sudoku.matrix[0][0] = 5;
+        sudoku.matrix[0][1] = 5;
+        let mut can = CandidateMatrix::from(sudoku);
+        assert!(matches!(
+            Pipeline::singles().run(&mut can),
+            Err(Contradiction::DuplicateValue { value: 5, .. })
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+//! 难度评级：每次只执行最简单的一步推演 以用到的最难技巧的权重作为评分
+//! 强制链与 Nishio 代价很高 轮到它们时一次执行该技巧的全部实例
+//! 权重参照 Sudoku Explainer 的数值 数组与鱼按规模区分 无法仅凭推演完成时视为需要猜测
+//! 推演中出现矛盾时 题目无解 不予评级
+
+use std::fmt;
+
+use super::{hint::next_steps, CandidateMatrix, Contradiction, Technique};
+
+/// 需要猜测时的评分
+pub const SEARCH_WEIGHT: f32 = 11.0;
+
+impl Technique {
+    /// 技巧的难度权重
+    pub fn weight(&self) -> f32 {
+        match self {
+            Technique::HiddenSingle => 1.5,
+            Technique::NakedSingle => 2.3,
+            Technique::LockedCandidates => 2.6,
+            Technique::NakedSubset { size: 2 } => 3.0,
+            Technique::Fish { size: 2 } => 3.2,
+            Technique::HiddenSubset { size: 2 } => 3.4,
+            Technique::NakedSubset { size: 3 } => 3.6,
+            Technique::Fish { size: 3 } => 3.8,
+            Technique::HiddenSubset { size: 3 } => 4.0,
+            Technique::TwoStrongLinks => 4.1,
+            Technique::Wing => 4.2,
+            Technique::EmptyRectangle => 4.3,
+            Technique::WWing => 4.4,
+            Technique::UniqueRectangle => 4.5,
+            Technique::FinnedFish { size: 2 } => 4.6,
+            Technique::FinnedFish { size: 3 } => 4.7,
+            Technique::NakedSubset { .. } => 5.0,
+            Technique::SueDeCoq => 5.1,
+            Technique::Fish { .. } => 5.2,
+            Technique::Coloring => 5.3,
+            Technique::HiddenSubset { .. } => 5.4,
+            Technique::FinnedFish { .. } => 5.5,
+            Technique::Bug => 5.6,
+            Technique::XChain => 6.6,
+            Technique::XYChain => 6.8,
+            Technique::AlsXz => 7.0,
+            Technique::AlternatingChain => 7.2,
+            Technique::AlsXyWing => 7.4,
+            Technique::DeathBlossom => 7.6,
+            Technique::PatternOverlay => 8.0,
+            Technique::ForcingChain => 8.5,
+            Technique::Nishio => 9.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    Extreme,
+}
+
+impl Difficulty {
+    fn of(score: f32) -> Difficulty {
+        match score {
+            s if s <= 1.5 => Difficulty::Easy,
+            s if s <= 2.6 => Difficulty::Medium,
+            s if s <= 4.0 => Difficulty::Hard,
+            s if s <= 6.0 => Difficulty::Expert,
+            _ => Difficulty::Extreme,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+            Difficulty::Extreme => "extreme",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub score: f32,
+    pub difficulty: Difficulty,
+    /// 用到的最难技巧 需要猜测时为仅凭推演能用到的最难技巧
+    pub hardest: Option<Technique>,
+    /// 仅凭推演无法完成
+    pub needs_search: bool,
+}
+
+/// 逐步执行最简单的推演直至完成 不使用依赖唯一解假设的技巧
+/// 每一步之后检查矛盾 出现矛盾说明题目无解
+pub fn rate(can: &CandidateMatrix) -> Result<Rating, Contradiction> {
+    let mut can = *can;
+    can.check()?;
+    let mut score: f32 = 0.0;
+    let mut hardest: Option<Technique> = None;
+    loop {
+        let steps = next_steps(&can, true);
+        let Some(step) = steps.first() else {
+            break;
+        };
+        if step.technique.weight() > score {
+            score = step.technique.weight();
+            hardest = Some(step.technique);
+        }
+        for step in steps.iter() {
+            can.apply_step(step);
+        }
+        can.check()?;
+    }
+    // 提示不会给出已有确定值的清理 完成时所有位置均已确定
+    let needs_search = can
+        .can_matrix
+        .iter()
+        .any(|ll| ll.iter().any(|c| c.count() != 1));
+    if needs_search {
+        score = SEARCH_WEIGHT;
+    }
+    Ok(Rating {
+        score,
+        difficulty: Difficulty::of(score),
+        hardest,
+        needs_search,
+    })
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} ({})", self.score, self.difficulty)?;
+        match self.hardest {
+            Some(technique) if !self.needs_search => write!(f, ", hardest: {}", technique.name()),
+            Some(technique) => write!(f, ", needs search after {}", technique.name()),
+            None if self.needs_search => write!(f, ", needs search"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        algorithm::hint::HINT_TECHNIQUES,
+        entity::SQUARE_OUTER_LEN,
+        fixture::{parse, HARD_SUDOKU},
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_hint_order_by_weight() {
+        for pair in HINT_TECHNIQUES.windows(2) {
+            assert!(pair[0].weight() < pair[1].weight());
+        }
+    }
+
+    #[test]
+    fn test_weight_by_size() {
+        for size in 2..4 {
+            let (smaller, larger) = (size, size + 1);
+            assert!(
+                Technique::Fish { size: smaller }.weight()
+                    < Technique::Fish { size: larger }.weight()
+            );
+            assert!(
+                Technique::NakedSubset { size: smaller }.weight()
+                    < Technique::NakedSubset { size: larger }.weight()
+            );
+            assert!(
+                Technique::HiddenSubset { size: smaller }.weight()
+                    < Technique::HiddenSubset { size: larger }.weight()
+            );
+        }
+        assert_eq!(Technique::Fish { size: 2 }.name(), "X-Wing");
+        assert_eq!(Technique::Fish { size: 3 }.weight(), 3.8);
+        assert_eq!(Technique::NakedSubset { size: 4 }.name(), "Naked Quad");
+    }
+
+    #[test]
+    fn test_rate_singles() {
+        init();
+
+        // 终盘去掉一行 仅需唯一位置
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        for col in 0..SQUARE_OUTER_LEN {
+            sudoku.matrix[0][col] = 0;
+        }
+        let rating = rate(&CandidateMatrix::from(sudoku)).unwrap();
+        assert_eq!(rating.score, 1.5);
+        assert_eq!(rating.difficulty, Difficulty::Easy);
+        assert_eq!(rating.hardest, Some(Technique::HiddenSingle));
+        assert!(!rating.needs_search);
+        assert_eq!(rating.to_string(), "1.5 (easy), hardest: Hidden Single");
+    }
+
+    #[test]
+    fn test_rate_medium() {
+        init();
+
+        // 按由易到难的顺序推演 仅需唯一候选值
+        let rating = rate(&CandidateMatrix::from(parse(HARD_SUDOKU[2].0))).unwrap();
+        assert!(!rating.needs_search);
+        assert_eq!(rating.score, 2.3);
+        assert_eq!(rating.difficulty, Difficulty::Medium);
+        assert_eq!(rating.hardest, Some(Technique::NakedSingle));
+    }
+
+    #[test]
+    fn test_rate_locked_candidates() {
+        init();
+
+        // Norvig 的 hard1 唯一位置与唯一候选值之外 还需区块排除
+        let sudoku = parse(
+            "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......",
+        );
+        let rating = rate(&CandidateMatrix::from(sudoku)).unwrap();
+        assert!(!rating.needs_search);
+        assert_eq!(rating.score, 2.6);
+        assert_eq!(rating.hardest, Some(Technique::LockedCandidates));
+    }
+
+    #[test]
+    fn test_rate_needs_search() {
+        init();
+
+        // 终盘去掉 7 与 9 构成的矩形 两解 仅凭推演无法完成
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        for (row, col) in [(0, 5), (0, 7), (1, 5), (1, 7)] {
+            sudoku.matrix[row][col] = 0;
+        }
+        let rating = rate(&CandidateMatrix::from(sudoku)).unwrap();
+        assert!(rating.needs_search);
+        assert_eq!(rating.score, SEARCH_WEIGHT);
+        assert_eq!(rating.difficulty, Difficulty::Extreme);
+        assert_eq!(rating.to_string(), "11.0 (extreme), needs search");
+    }
+
+    #[test]
+    fn test_rate_no_solution() {
+        init();
+
+        let mut sudoku = parse(HARD_SUDOKU[2].0);
+        sudoku.matrix[0][0] = 5;
+        sudoku.matrix[0][1] = 5;
+        // 无解的题目不予评级
+        assert!(matches!(
+            rate(&CandidateMatrix::from(sudoku)),
+            Err(Contradiction::DuplicateValue { value: 5, .. })
+        ));
+    }
+}
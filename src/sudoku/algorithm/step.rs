@@ -0,0 +1,415 @@
+//! 推演步骤记录：技巧每找到一个实例 记录构成该实例的位置与数值
+//! 以及由此确定的值与标记黑名单的候选值 并生成可读的说明
+
+use crate::sudoku::{
+    entity::{SudokuValueType, SQUARE_OUTER_LEN},
+    rulers::Position,
+};
+
+use super::{
+    cell_name, cells_name, chain::ChainKind, is_peer, Candidate, CandidateMatrix, NishioBudget,
+    DEFAULT_FORCING_DEPTH,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    LockedCandidates,
+    /// size 为数值个数 2、3、4 分别为 Pair、Triple、Quad
+    HiddenSubset {
+        size: usize,
+    },
+    NakedSubset {
+        size: usize,
+    },
+    /// size 为基础分区个数 2、3、4 分别为 X-Wing、Swordfish、Jellyfish
+    Fish {
+        size: usize,
+    },
+    FinnedFish {
+        size: usize,
+    },
+    Wing,
+    TwoStrongLinks,
+    EmptyRectangle,
+    WWing,
+    Coloring,
+    XChain,
+    XYChain,
+    AlternatingChain,
+    AlsXz,
+    AlsXyWing,
+    DeathBlossom,
+    SueDeCoq,
+    PatternOverlay,
+    /// 推演轮数由 ForcingChainStrategy 指定 单独使用时为 DEFAULT_FORCING_DEPTH
+    ForcingChain,
+    /// 上限与是否假设唯一解由 NishioStrategy 指定 单独使用时为默认上限且不假设唯一解
+    Nishio,
+    UniqueRectangle,
+    Bug,
+}
+
+/// 依赖唯一解假设的技巧
+pub const UNIQUENESS_TECHNIQUES: [Technique; 2] = [Technique::UniqueRectangle, Technique::Bug];
+
+impl Technique {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "Naked Single",
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::LockedCandidates => "Locked Candidates",
+            Technique::HiddenSubset { size: 2 } => "Hidden Pair",
+            Technique::HiddenSubset { size: 3 } => "Hidden Triple",
+            Technique::HiddenSubset { .. } => "Hidden Quad",
+            Technique::NakedSubset { size: 2 } => "Naked Pair",
+            Technique::NakedSubset { size: 3 } => "Naked Triple",
+            Technique::NakedSubset { .. } => "Naked Quad",
+            Technique::Fish { size: 2 } => "X-Wing",
+            Technique::Fish { size: 3 } => "Swordfish",
+            Technique::Fish { .. } => "Jellyfish",
+            Technique::FinnedFish { size: 2 } => "Finned X-Wing",
+            Technique::FinnedFish { size: 3 } => "Finned Swordfish",
+            Technique::FinnedFish { .. } => "Finned Jellyfish",
+            Technique::Wing => "XY-Wing / XYZ-Wing",
+            Technique::TwoStrongLinks => "Skyscraper / Two-String Kite",
+            Technique::EmptyRectangle => "Empty Rectangle",
+            Technique::WWing => "W-Wing",
+            Technique::Coloring => "Coloring",
+            Technique::XChain => "X-Chain",
+            Technique::XYChain => "XY-Chain",
+            Technique::AlternatingChain => "Alternating Inference Chain",
+            Technique::AlsXz => "ALS-XZ",
+            Technique::AlsXyWing => "ALS-XY-Wing",
+            Technique::DeathBlossom => "Death Blossom",
+            Technique::SueDeCoq => "Sue de Coq",
+            Technique::PatternOverlay => "Pattern Overlay",
+            Technique::ForcingChain => "Forcing Chain",
+            Technique::Nishio => "Nishio",
+            Technique::UniqueRectangle => "Unique Rectangle",
+            Technique::Bug => "BUG+1",
+        }
+    }
+
+    /// 基于假设推演的技巧 在副本上反复推演 代价远高于其他技巧
+    pub fn is_assumption(&self) -> bool {
+        matches!(self, Technique::ForcingChain | Technique::Nishio)
+    }
+}
+
+/// 技巧的一个实例
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub technique: Technique,
+    /// 构成该实例的位置 如鱼的基础分区内的位置、枢纽与钳子、链上的节点、待定数组
+    pub cells: Vec<Position>,
+    /// 构成该实例的数值
+    pub values: Vec<SudokuValueType>,
+    /// 指明位置与数值的说明
+    pub reason: String,
+    /// 由此确定的位置与数值
+    pub placements: Vec<(Position, SudokuValueType)>,
+    /// 标记黑名单的位置与数值 不含已确定的位置
+    pub eliminations: Vec<(Position, SudokuValueType)>,
+}
+
+impl Step {
+    /// 说明在实例生效时由记录器生成
+    pub(super) fn new(technique: Technique, cells: Vec<Position>, value_ids: &[usize]) -> Step {
+        Step {
+            technique,
+            cells,
+            values: value_ids.iter().map(|value_id| value_id + 1).collect(),
+            reason: String::new(),
+            placements: Vec::new(),
+            eliminations: Vec::new(),
+        }
+    }
+
+    pub(super) fn place(&mut self, pos: Position, value_id: usize) {
+        self.placements.push((pos, value_id + 1));
+    }
+
+    pub(super) fn eliminate(&mut self, pos: Position, value_id: usize) {
+        self.eliminations.push((pos, value_id + 1));
+    }
+
+    /// 同时可见所有给定位置的其他位置 均不可能为该值
+    pub(super) fn eliminate_common_peers(&mut self, value_id: usize, positions: &[Position]) {
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                if positions.iter().all(|pos| is_peer(pos, &(row, col))) {
+                    self.eliminate((row, col), value_id);
+                }
+            }
+        }
+    }
+
+    /// 仅保留在当前候选矩阵上仍会生效的结论 去掉重复
+    fn retain_effective(&mut self, can: &CandidateMatrix) {
+        let mut placements: Vec<(Position, SudokuValueType)> = Vec::new();
+        for ((row, col), value) in self.placements.iter() {
+            let current = can.can_matrix[*row][*col];
+            if current.only() != Some(*value)
+                && !placements.iter().any(|(pos, _)| *pos == (*row, *col))
+            {
+                placements.push(((*row, *col), *value));
+            }
+        }
+        let mut eliminations: Vec<(Position, SudokuValueType)> = Vec::new();
+        for (pos, value) in self.eliminations.iter() {
+            let is_placed = placements.iter().any(|(placed, _)| placed == pos);
+            if !is_placed
+                && can.can_matrix[pos.0][pos.1].has(value - 1)
+                && !eliminations.contains(&(*pos, *value))
+            {
+                eliminations.push((*pos, *value));
+            }
+        }
+        self.placements = placements;
+        self.eliminations = eliminations;
+    }
+
+    pub fn explanation(&self) -> String {
+        let mut text = format!("{}: {}.", self.technique.name(), self.reason);
+        if !self.placements.is_empty() {
+            let placements: Vec<String> = self
+                .placements
+                .iter()
+                .map(|(pos, value)| format!("{}={}", cell_name(pos), value))
+                .collect();
+            text.push_str(&format!(" place {};", placements.join(", ")));
+        }
+        for value in 1..=SQUARE_OUTER_LEN {
+            let cells: Vec<Position> = self
+                .eliminations
+                .iter()
+                .filter(|(_, v)| *v == value)
+                .map(|(pos, _)| *pos)
+                .collect();
+            if !cells.is_empty() {
+                text.push_str(&format!(
+                    " eliminate {} from {};",
+                    value,
+                    cells_name(&cells)
+                ));
+            }
+        }
+        text
+    }
+}
+
+/// 逐个执行技巧实例并记录 仅需第一个实例时 之后的实例不再执行
+pub(super) struct Recorder {
+    steps: Vec<Step>,
+    is_first_only: bool,
+    /// 仅执行不记录 无需生成说明
+    is_silent: bool,
+}
+
+impl Recorder {
+    pub(super) fn new(is_first_only: bool) -> Recorder {
+        Recorder {
+            steps: Vec::new(),
+            is_first_only,
+            is_silent: false,
+        }
+    }
+
+    pub(super) fn silent() -> Recorder {
+        Recorder {
+            is_silent: true,
+            ..Recorder::new(false)
+        }
+    }
+
+    /// 已得到所需的实例 技巧可提前结束
+    pub(super) fn is_done(&self) -> bool {
+        self.is_first_only && !self.steps.is_empty()
+    }
+
+    /// 执行实例中尚未生效的结论 有变化时生成说明并记录
+    pub(super) fn apply<F>(&mut self, can: &mut CandidateMatrix, mut step: Step, reason: F)
+    where
+        F: FnOnce() -> String,
+    {
+        if self.is_done() {
+            return;
+        }
+        step.retain_effective(can);
+        if step.placements.is_empty() && step.eliminations.is_empty() {
+            return;
+        }
+        can.apply_step(&step);
+        if self.is_silent {
+            return;
+        }
+        step.reason = reason();
+        self.steps.push(step);
+    }
+}
+
+impl CandidateMatrix {
+    /// 执行某一步推演的结论
+    pub fn apply_step(&mut self, step: &Step) {
+        for ((row, col), value) in step.placements.iter() {
+            self.can_matrix[*row][*col] = Candidate::new_only(value - 1);
+        }
+        for ((row, col), value) in step.eliminations.iter() {
+            self.can_matrix[*row][*col].remove(value - 1);
+        }
+    }
+
+    pub(super) fn record(&mut self, technique: Technique, recorder: &mut Recorder) {
+        match technique {
+            Technique::NakedSingle => self.record_naked_single(recorder),
+            Technique::HiddenSingle => self.record_check_position(true, false, recorder),
+            Technique::LockedCandidates => self.record_check_position(false, true, recorder),
+            Technique::HiddenSubset { size } => self.record_position_mutex(size..=size, recorder),
+            Technique::NakedSubset { size } => self.record_value_mutex(size..=size, recorder),
+            Technique::Fish { size } => self.record_fish(size..=size, recorder),
+            Technique::FinnedFish { size } => self.record_finned_fish(size..=size, recorder),
+            Technique::Wing => self.record_wing(recorder),
+            Technique::TwoStrongLinks => self.record_two_strong_links(recorder),
+            Technique::EmptyRectangle => self.record_empty_rectangle(recorder),
+            Technique::WWing => self.record_w_wing(recorder),
+            Technique::Coloring => self.record_coloring(recorder),
+            Technique::XChain => self.record_inference_chain(ChainKind::X, recorder),
+            Technique::XYChain => self.record_inference_chain(ChainKind::XY, recorder),
+            Technique::AlternatingChain => {
+                self.record_inference_chain(ChainKind::Alternating, recorder)
+            }
+            Technique::AlsXz => self.record_als_xz(recorder),
+            Technique::AlsXyWing => self.record_als_xy_wing(recorder),
+            Technique::DeathBlossom => self.record_death_blossom(recorder),
+            Technique::SueDeCoq => self.record_sue_de_coq(recorder),
+            Technique::PatternOverlay => self.record_pattern_overlay(recorder),
+            Technique::ForcingChain => self.record_forcing_chain(DEFAULT_FORCING_DEPTH, recorder),
+            Technique::Nishio => self.record_nishio(NishioBudget::DEFAULT, false, recorder),
+            Technique::UniqueRectangle => self.record_unique_rectangle(recorder),
+            Technique::Bug => self.record_bug(recorder),
+        }
+    }
+
+    /// 执行某一技巧的第一个实例 有变化时返回记录
+    pub fn evolution_by_technique(&mut self, technique: Technique) -> Option<Step> {
+        let mut recorder = Recorder::new(true);
+        self.record(technique, &mut recorder);
+        recorder.steps.pop()
+    }
+
+    /// 执行一轮某一技巧的全部实例 依次返回记录
+    pub fn steps_by_technique(&mut self, technique: Technique) -> Vec<Step> {
+        self.steps_by(|can, recorder| can.record(technique, recorder))
+    }
+
+    /// 以给定的推演执行一轮全部实例 依次返回记录
+    pub(super) fn steps_by<F>(&mut self, evolution: F) -> Vec<Step>
+    where
+        F: FnOnce(&mut CandidateMatrix, &mut Recorder),
+    {
+        let mut recorder = Recorder::new(false);
+        evolution(self, &mut recorder);
+        recorder.steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        algorithm::{pattern_techniques, ForcingChainStrategy, NishioStrategy, Strategy},
+        fixture::{assert_evolution_keeps_solution, candidate_of},
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[5]);
+        can.can_matrix[0][1] = candidate_of(&[5, 6]);
+        // 每次仅执行一个实例 (0,0) 的 5 从同行同列同九宫格的其他位置排除
+        let step = can.evolution_by_technique(Technique::NakedSingle).unwrap();
+        assert_eq!(step.technique, Technique::NakedSingle);
+        assert_eq!(step.cells, vec![(0, 0)]);
+        assert_eq!(step.values, vec![5]);
+        assert!(step.placements.is_empty());
+        assert_eq!(step.eliminations.len(), 8 + 8 + 4);
+        assert!(step.eliminations.contains(&((0, 1), 5)));
+        assert!(step.eliminations.contains(&((8, 0), 5)));
+        assert!(step
+            .explanation()
+            .starts_with("Naked Single: r1c1 can only be 5. eliminate 5 from r1c2, "));
+        assert_eq!(can.can_matrix[0][1], candidate_of(&[6]));
+        assert_eq!(can.can_matrix[8][8], Candidate::new_all());
+
+        // (0,0) 已无可排除的候选值 下一个实例为 (0,1)
+        let step = can.evolution_by_technique(Technique::NakedSingle).unwrap();
+        assert_eq!(step.cells, vec![(0, 1)]);
+        assert_eq!(step.eliminations.len(), 8 + 8 + 4 - 1);
+        assert_eq!(can.evolution_by_technique(Technique::NakedSingle), None);
+
+        assert_eq!(
+            can.evolution_by_technique(Technique::Fish { size: 2 }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_steps_by_technique() {
+        init();
+
+        // 第 1 行与第 1 列各有一个唯一位置
+        let mut can = CandidateMatrix::new();
+        for col in 1..9 {
+            can.can_matrix[0][col].remove(2);
+        }
+        for row in 1..9 {
+            can.can_matrix[row][8].remove(6);
+        }
+        let mut origin = can;
+        let steps = can.steps_by_technique(Technique::HiddenSingle);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].placements, vec![((0, 0), 3)]);
+        assert_eq!(steps[0].cells.len(), 9);
+        assert_eq!(
+            steps[0].explanation(),
+            "Hidden Single: r1c1 is the only place for 3 in row 1. place r1c1=3;"
+        );
+        assert_eq!(steps[1].placements, vec![((0, 8), 7)]);
+        assert!(steps[1].reason.ends_with("in column 9"));
+
+        // 重放所有实例 与一次执行的结果一致
+        for step in steps.iter() {
+            origin.apply_step(step);
+        }
+        assert!(origin == can);
+    }
+
+    #[test]
+    fn test_techniques_keep_solution() {
+        // 各技巧的具体实例见各自的模块 这里只检查全部技巧一起推演时不会排除答案
+        let nishio = NishioStrategy {
+            budget: NishioBudget {
+                depth: 1,
+                passes: 5,
+                deadline: None,
+            },
+            assume_unique: true,
+        };
+        assert_evolution_keeps_solution(|can| {
+            let origin = *can;
+            for technique in pattern_techniques(true) {
+                can.steps_by_technique(technique);
+            }
+            if origin == *can {
+                ForcingChainStrategy::default().apply(can);
+                nishio.apply(can);
+            }
+        });
+    }
+}
@@ -0,0 +1,179 @@
+//! 强链：某一分区 某一数值 仅有两个位置可选 二者必有其一为该值
+//! 双强链（Skyscraper、Two-String Kite 等）：两条强链各有一端互相可见 这两端至多一个为该值
+//! 则另外两端必有其一为该值 -- 可将同时可见另外两端的位置标记该值的黑名单
+//! 空矩形（Empty Rectangle）：九宫格内的位置均在一行一列组成的十字上
+//! 强链一端位于十字的行上 则该端为真时 九宫格内仅十字的列可选
+//! -- 可将十字的列上 九宫格以外 且可见强链另一端的位置标记该值的黑名单 行列互换同理
+
+use crate::sudoku::{
+    entity::SQUARE_OUTER_LEN,
+    rulers::{get_sudoku_ruler_loop, Position, BOX_RULER_ID},
+};
+
+use super::{cell_name, is_peer, partition_name, step::Recorder, CandidateMatrix, Step, Technique};
+
+impl CandidateMatrix {
+    pub fn evolution_by_two_strong_links(&mut self) {
+        self.record_two_strong_links(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_two_strong_links(&mut self, recorder: &mut Recorder) {
+        for value_id in 0..SQUARE_OUTER_LEN {
+            let links = self.strong_links(value_id);
+            for (i, first) in links.iter().enumerate() {
+                for second in links.iter().skip(i + 1) {
+                    for (a, a_end) in [(first.0, first.1), (first.1, first.0)] {
+                        for (b, b_end) in [(second.0, second.1), (second.1, second.0)] {
+                            if recorder.is_done() {
+                                return;
+                            }
+                            let ends = [a, a_end, b, b_end];
+                            let distinct = ends
+                                .iter()
+                                .enumerate()
+                                .all(|(i, pos)| !ends[i + 1..].contains(pos));
+                            if !distinct || !is_peer(&a_end, &b_end) {
+                                continue;
+                            }
+                            let mut step =
+                                Step::new(Technique::TwoStrongLinks, ends.to_vec(), &[value_id]);
+                            step.eliminate_common_peers(value_id, &[a, b]);
+                            recorder.apply(self, step, || {
+                                format!(
+                                    "strong links of {} {}-{} and {}-{} meet at {} and {}",
+                                    value_id + 1,
+                                    cell_name(&a),
+                                    cell_name(&a_end),
+                                    cell_name(&b),
+                                    cell_name(&b_end),
+                                    cell_name(&a_end),
+                                    cell_name(&b_end)
+                                )
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn evolution_by_empty_rectangle(&mut self) {
+        self.record_empty_rectangle(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_empty_rectangle(&mut self, recorder: &mut Recorder) {
+        let ruler_loop = get_sudoku_ruler_loop();
+        for value_id in 0..SQUARE_OUTER_LEN {
+            let links = self.strong_links(value_id);
+            for (box_id, box_partition) in ruler_loop[BOX_RULER_ID].partitions.iter().enumerate() {
+                let candidates: Vec<Position> = box_partition
+                    .iter()
+                    .filter(|(row, col)| self.can_matrix[*row][*col].has(value_id))
+                    .copied()
+                    .collect();
+                if candidates.len() < 2 {
+                    continue;
+                }
+                for (row, col) in box_partition.iter() {
+                    // 九宫格内位置均在十字上
+                    if !candidates.iter().all(|pos| pos.0 == *row || pos.1 == *col) {
+                        continue;
+                    }
+                    for (near, far) in links.iter().flat_map(|(a, b)| [(*a, *b), (*b, *a)]) {
+                        if recorder.is_done() {
+                            return;
+                        }
+                        if box_partition.contains(&near) {
+                            continue;
+                        }
+                        let mut cells = candidates.clone();
+                        cells.extend([near, far]);
+                        let mut step = Step::new(Technique::EmptyRectangle, cells, &[value_id]);
+                        for i in 0..SQUARE_OUTER_LEN {
+                            let target = if near.0 == *row {
+                                (i, *col)
+                            } else if near.1 == *col {
+                                (*row, i)
+                            } else {
+                                break;
+                            };
+                            if !box_partition.contains(&target) && is_peer(&target, &far) {
+                                step.eliminate(target, value_id);
+                            }
+                        }
+                        recorder.apply(self, step, || {
+                            format!(
+                                "{} in {} lies on row {} and column {}, with the strong link {}-{}",
+                                value_id + 1,
+                                partition_name(BOX_RULER_ID, box_id),
+                                row + 1,
+                                col + 1,
+                                cell_name(&near),
+                                cell_name(&far)
+                            )
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        fixture::{column, keep_only, line},
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_skyscraper() {
+        init();
+
+        // 1 在第 1 列仅能位于 2、7 行 第 4 列仅能位于 0、7 行
+        let mut can = CandidateMatrix::new();
+        keep_only(&mut can, 0, &column(1), &[(2, 1), (7, 1)]);
+        keep_only(&mut can, 0, &column(4), &[(0, 4), (7, 4)]);
+        can.evolution_by_two_strong_links();
+        for pos in [(0, 0), (0, 2), (2, 3), (2, 5)] {
+            assert!(!can.can_matrix[pos.0][pos.1].has(0));
+        }
+        assert!(can.can_matrix[2][1].has(0));
+        assert!(can.can_matrix[0][4].has(0));
+        assert!(can.can_matrix[1][3].has(0));
+        assert!(can.can_matrix[2][6].has(0));
+    }
+
+    #[test]
+    fn test_two_string_kite() {
+        init();
+
+        // 2 在第 0 行仅能位于 1、6 列 第 2 列仅能位于 1、5 行 (0, 1) 与 (1, 2) 同九宫格
+        let mut can = CandidateMatrix::new();
+        keep_only(&mut can, 1, &line(0), &[(0, 1), (0, 6)]);
+        keep_only(&mut can, 1, &column(2), &[(1, 2), (5, 2)]);
+        can.evolution_by_two_strong_links();
+        assert!(!can.can_matrix[5][6].has(1));
+        assert!(can.can_matrix[5][5].has(1));
+        assert!(can.can_matrix[4][6].has(1));
+    }
+
+    #[test]
+    fn test_empty_rectangle() {
+        init();
+
+        // 3 在第一个九宫格内仅能位于第 1 行与第 1 列 第 5 列仅能位于 1、7 行
+        let mut can = CandidateMatrix::new();
+        for pos in [(0, 0), (0, 2), (2, 0), (2, 2)] {
+            can.can_matrix[pos.0][pos.1].remove(2);
+        }
+        keep_only(&mut can, 2, &column(5), &[(1, 5), (7, 5)]);
+        can.evolution_by_empty_rectangle();
+        assert!(!can.can_matrix[7][1].has(2));
+        assert!(can.can_matrix[6][1].has(2));
+        assert!(can.can_matrix[7][0].has(2));
+        assert!(can.can_matrix[1][1].has(2));
+    }
+}
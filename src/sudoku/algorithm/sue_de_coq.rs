@@ -0,0 +1,191 @@
+//! Sue de Coq：九宫格与行（列）相交处的多个位置 候选值比位置数至少多两个
+//! 另取行（列）其余部分的若干位置 与九宫格其余部分的若干位置 两部分候选值不相交
+//! 三部分位置合计 N 个 候选值合计也为 N 个 则每个候选值恰好出现一次
+//! -- 可将行（列）其余位置中 九宫格部分以外的候选值标记黑名单 九宫格同理
+
+use crate::sudoku::rulers::{
+    get_sudoku_ruler_loop, Position, BOX_RULER_ID, COL_RULER_ID, ROW_RULER_ID,
+};
+
+use super::{
+    candidate_name, cells_name, combinations, step::Recorder, Candidate, CandidateMatrix, Step,
+    Technique,
+};
+
+/// 一组位置的候选值并集
+fn union_of(can: &CandidateMatrix, positions: &[Position]) -> Candidate {
+    let mut union = Candidate::new_none();
+    for (row, col) in positions.iter() {
+        union = union.union(&can.can_matrix[*row][*col]);
+    }
+    union
+}
+
+/// 从给定位置中选取所有非空子集
+fn subsets(positions: &[Position]) -> Vec<Vec<Position>> {
+    let mut result = Vec::new();
+    for size in 1..=positions.len() {
+        for group in combinations(positions.len(), size) {
+            result.push(group.iter().map(|i| positions[*i]).collect());
+        }
+    }
+    result
+}
+
+impl CandidateMatrix {
+    fn unknown_positions(&self, positions: &[Position]) -> Vec<Position> {
+        positions
+            .iter()
+            .filter(|(row, col)| self.can_matrix[*row][*col].count() > 1)
+            .copied()
+            .collect()
+    }
+
+    pub fn evolution_by_sue_de_coq(&mut self) {
+        self.record_sue_de_coq(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_sue_de_coq(&mut self, recorder: &mut Recorder) {
+        let ruler_loop = get_sudoku_ruler_loop();
+        for box_partition in ruler_loop[BOX_RULER_ID].partitions.iter() {
+            for line_ruler_id in [ROW_RULER_ID, COL_RULER_ID] {
+                for line in ruler_loop[line_ruler_id].partitions.iter() {
+                    let cross: Vec<Position> = line
+                        .iter()
+                        .filter(|pos| box_partition.contains(pos))
+                        .copied()
+                        .collect();
+                    if cross.len() < 2 {
+                        continue;
+                    }
+                    let line_rest: Vec<Position> = line
+                        .iter()
+                        .filter(|pos| !cross.contains(pos))
+                        .copied()
+                        .collect();
+                    let box_rest: Vec<Position> = box_partition
+                        .iter()
+                        .filter(|pos| !cross.contains(pos))
+                        .copied()
+                        .collect();
+                    self.sue_de_coq(&cross, &line_rest, &box_rest, recorder);
+                }
+            }
+        }
+    }
+
+    fn sue_de_coq(
+        &mut self,
+        cross: &[Position],
+        line_rest: &[Position],
+        box_rest: &[Position],
+        recorder: &mut Recorder,
+    ) {
+        let cross_unknown = self.unknown_positions(cross);
+        let line_subsets = subsets(&self.unknown_positions(line_rest));
+        let box_subsets = subsets(&self.unknown_positions(box_rest));
+        for size in 2..=cross_unknown.len() {
+            for group in combinations(cross_unknown.len(), size) {
+                let cells: Vec<Position> = group.iter().map(|i| cross_unknown[*i]).collect();
+                let cross_union = union_of(self, &cells);
+                if cross_union.count() < size + 2 {
+                    continue;
+                }
+                for line_cells in line_subsets.iter() {
+                    let line_union = union_of(self, line_cells);
+                    for box_cells in box_subsets.iter() {
+                        if recorder.is_done() {
+                            return;
+                        }
+                        let box_union = union_of(self, box_cells);
+                        let total = cross_union.union(&line_union).union(&box_union);
+                        if !line_union.intersection(&box_union).is_empty()
+                            || total.count() != size + line_cells.len() + box_cells.len()
+                        {
+                            continue;
+                        }
+                        let mut pattern = cells.clone();
+                        pattern.extend(line_cells.iter().chain(box_cells.iter()));
+                        let mut step = Step::new(
+                            Technique::SueDeCoq,
+                            pattern,
+                            &total.iter().collect::<Vec<usize>>(),
+                        );
+                        // 不在九宫格部分的值 只能出现在相交处或行（列）部分 九宫格同理
+                        for value_id in total.iter() {
+                            if !box_union.has(value_id) {
+                                eliminate_rest(&mut step, value_id, line_rest, line_cells);
+                            }
+                            if !line_union.has(value_id) {
+                                eliminate_rest(&mut step, value_id, box_rest, box_cells);
+                            }
+                        }
+                        recorder.apply(self, step, || {
+                            format!(
+                                "{} {} with {} {} of the line and {} {} of the box",
+                                cells_name(&cells),
+                                candidate_name(&cross_union),
+                                cells_name(line_cells),
+                                candidate_name(&line_union),
+                                cells_name(box_cells),
+                                candidate_name(&box_union)
+                            )
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn eliminate_rest(step: &mut Step, value_id: usize, rest: &[Position], kept: &[Position]) {
+    for pos in rest.iter().filter(|pos| !kept.contains(pos)) {
+        step.eliminate(*pos, value_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{fixture::candidate_of, rulers::init};
+
+    use super::*;
+
+    #[test]
+    fn test_sue_de_coq() {
+        init();
+
+        // 相交处 (0,0) (0,1) 共 {1,2,3,4} (0,2) 已确定
+        // 行部分 (0,5){1,2} 九宫格部分 (1,1){3,4}
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2, 3, 4]);
+        can.can_matrix[0][1] = candidate_of(&[1, 2, 3, 4]);
+        can.can_matrix[0][2] = candidate_of(&[9]);
+        can.can_matrix[0][5] = candidate_of(&[1, 2]);
+        can.can_matrix[1][1] = candidate_of(&[3, 4]);
+        can.evolution_by_sue_de_coq();
+        // 行的其他位置排除 1 2 九宫格的其他位置排除 3 4
+        assert_eq!(can.can_matrix[0][8], candidate_of(&[3, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(can.can_matrix[2][2], candidate_of(&[1, 2, 5, 6, 7, 8, 9]));
+        assert_eq!(can.can_matrix[0][0], candidate_of(&[1, 2, 3, 4]));
+        assert_eq!(can.can_matrix[0][5], candidate_of(&[1, 2]));
+        assert_eq!(can.can_matrix[1][1], candidate_of(&[3, 4]));
+        // 其他分区不受影响
+        assert_eq!(can.can_matrix[4][4], Candidate::new_all());
+    }
+
+    #[test]
+    fn test_sue_de_coq_extra_value() {
+        init();
+
+        // 相交处三个位置 {1,2,3,4,5} 多出的 5 只能出现在相交处
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2, 3, 4, 5]);
+        can.can_matrix[0][1] = candidate_of(&[1, 2, 3, 4, 5]);
+        can.can_matrix[0][2] = candidate_of(&[1, 2, 3, 4, 5]);
+        can.can_matrix[0][5] = candidate_of(&[1, 2]);
+        can.can_matrix[1][1] = candidate_of(&[3, 4]);
+        can.evolution_by_sue_de_coq();
+        assert_eq!(can.can_matrix[0][8], candidate_of(&[3, 4, 6, 7, 8, 9]));
+        assert_eq!(can.can_matrix[2][2], candidate_of(&[1, 2, 6, 7, 8, 9]));
+    }
+}
@@ -0,0 +1,443 @@
+//! 唯一性技巧：假设数独仅有唯一解 因此不能出现可以互换的致命结构
+//! 这些技巧依赖假设 仅在明确假设唯一解时使用
+//!
+//! 唯一矩形：位于两行、两列、两个九宫格的四个位置 均含有候选值 {a,b}
+//! 若四个位置均仅剩 {a,b} 则 a b 可以互换 解不唯一 因此多余的候选值中至少一个为真
+//! Type 1：仅一个位置有多余候选值  -- 可将该位置的 a b 标记黑名单
+//! Type 2/5：有多余候选值的位置 多余候选值均仅为同一个 c  -- 可将同时可见这些位置的其他位置标记 c 的黑名单
+//! Type 3：两个相邻位置的多余候选值视为一个虚拟位置 与分区内其他位置组成数组  -- 可将分区其他位置的这些数值标记黑名单
+//! Type 4：两个相邻位置所在分区 a 仅能位于这两个位置  -- 可将这两个位置的 b 标记黑名单
+//! Type 6：对角两个位置仅剩 {a,b} 两行（列）中 a 仅能位于矩形内  -- 可将另外两个位置的 a 标记黑名单
+//! Hidden：某一位置仅剩 {a,b} 对角位置所在行与列中 a 仅能位于矩形内  -- 可将对角位置的 b 标记黑名单
+//!
+//! BUG+1：除一个三值位置外 其他未确定位置均仅剩两个候选值
+//! 若去掉三值位置的某一候选值后 每个分区的每个数值均恰好有零或两个位置可选 则解不唯一  -- 该候选值可确定
+
+use crate::sudoku::{
+    entity::SQUARE_OUTER_LEN,
+    rulers::{
+        each_sudoku_partition, get_sudoku_ruler_partition_map, Position, PositionPartition,
+        BOX_RULER_ID, COL_RULER_ID, ROW_RULER_ID,
+    },
+};
+
+use super::{
+    candidate_name, cell_name, cells_name, combinations, is_peer, step::Recorder, Candidate,
+    CandidateMatrix, Step, Technique, MAX_SUBSET_LEN,
+};
+
+/// 两个位置共同所在的分区
+fn common_partitions(a: &Position, b: &Position) -> Vec<PositionPartition> {
+    get_sudoku_ruler_partition_map(a)
+        .iter()
+        .filter(|partition| partition.contains(b))
+        .copied()
+        .collect()
+}
+
+impl CandidateMatrix {
+    /// 分区内某一数值 仅能位于给定位置
+    fn is_only_positions(
+        &self,
+        partition: &PositionPartition,
+        value_id: usize,
+        positions: &[Position],
+    ) -> bool {
+        partition
+            .iter()
+            .all(|pos| positions.contains(pos) || !self.can_matrix[pos.0][pos.1].has(value_id))
+    }
+
+    pub fn evolution_by_unique_rectangle(&mut self) {
+        self.record_unique_rectangle(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_unique_rectangle(&mut self, recorder: &mut Recorder) {
+        for r1 in 0..SQUARE_OUTER_LEN {
+            for r2 in (r1 + 1)..SQUARE_OUTER_LEN {
+                for c1 in 0..SQUARE_OUTER_LEN {
+                    for c2 in (c1 + 1)..SQUARE_OUTER_LEN {
+                        let corners = [(r1, c1), (r1, c2), (r2, c1), (r2, c2)];
+                        let mut boxes: Vec<Position> = corners
+                            .iter()
+                            .map(|pos| get_sudoku_ruler_partition_map(pos)[BOX_RULER_ID][0])
+                            .collect();
+                        boxes.sort();
+                        boxes.dedup();
+                        if boxes.len() != 2 {
+                            continue;
+                        }
+                        for (a, b) in combinations(SQUARE_OUTER_LEN, 2)
+                            .iter()
+                            .map(|pair| (pair[0], pair[1]))
+                        {
+                            if recorder.is_done() {
+                                return;
+                            }
+                            self.unique_rectangle(&corners, a, b, recorder);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// corners 依次为 左上 右上 左下 右下
+    fn unique_rectangle(
+        &mut self,
+        corners: &[Position; 4],
+        a: usize,
+        b: usize,
+        recorder: &mut Recorder,
+    ) {
+        if !corners.iter().all(|pos| {
+            let can = self.can_matrix[pos.0][pos.1];
+            can.has(a) && can.has(b)
+        }) {
+            return;
+        }
+        let is_bivalue = |pos: &Position| self.can_matrix[pos.0][pos.1].count() == 2;
+        let floor: Vec<Position> = corners
+            .iter()
+            .filter(|pos| is_bivalue(pos))
+            .copied()
+            .collect();
+        let roof: Vec<Position> = corners
+            .iter()
+            .filter(|pos| !is_bivalue(pos))
+            .copied()
+            .collect();
+        if roof.is_empty() {
+            // 已是致命结构 数独本身不唯一
+            return;
+        }
+        // 多余的候选值
+        let extras: Vec<Candidate> = roof
+            .iter()
+            .map(|pos| {
+                let mut can = self.can_matrix[pos.0][pos.1];
+                can.remove(a);
+                can.remove(b);
+                can
+            })
+            .collect();
+        let rectangle_step = || Step::new(Technique::UniqueRectangle, corners.to_vec(), &[a, b]);
+        let rectangle_reason = |kind: &str, extra: &str| {
+            format!(
+                "{} would hold only {}, {} ({}{})",
+                cells_name(corners),
+                a + 1,
+                b + 1,
+                kind,
+                extra
+            )
+        };
+
+        // Type 1
+        if roof.len() == 1 {
+            let mut step = rectangle_step();
+            step.eliminate(roof[0], a);
+            step.eliminate(roof[0], b);
+            recorder.apply(self, step, || rectangle_reason("type 1", ""));
+            return;
+        }
+
+        // Type 2/5
+        if extras.iter().all(|can| *can == extras[0]) {
+            if let Some(value) = extras[0].only() {
+                let mut step = rectangle_step();
+                step.eliminate_common_peers(value - 1, &roof);
+                recorder.apply(self, step, || {
+                    let extra = format!(", one of {} is {}", cells_name(&roof), value);
+                    rectangle_reason("type 2", &extra)
+                });
+            }
+        }
+
+        if roof.len() == 2 && is_peer(&roof[0], &roof[1]) {
+            let mut union = Candidate::new_none();
+            for extra in extras.iter() {
+                union = union.union(extra);
+            }
+            for partition in common_partitions(&roof[0], &roof[1]) {
+                // Type 3
+                let others: Vec<Position> = partition
+                    .iter()
+                    .filter(|pos| !roof.contains(pos) && self.can_matrix[pos.0][pos.1].count() > 1)
+                    .copied()
+                    .collect();
+                for size in 1..MAX_SUBSET_LEN {
+                    for group in combinations(others.len(), size) {
+                        let cells: Vec<Position> = group.iter().map(|i| others[*i]).collect();
+                        let mut subset = union;
+                        for (row, col) in cells.iter() {
+                            subset = subset.union(&self.can_matrix[*row][*col]);
+                        }
+                        if subset.count() != size + 1 {
+                            continue;
+                        }
+                        let mut step = rectangle_step();
+                        step.cells.extend(cells.iter());
+                        for pos in partition.iter() {
+                            if roof.contains(pos) || cells.contains(pos) {
+                                continue;
+                            }
+                            for value_id in subset.iter() {
+                                step.eliminate(*pos, value_id);
+                            }
+                        }
+                        recorder.apply(self, step, || {
+                            let extra = format!(
+                                ", {} {} with {} form a subset",
+                                cells_name(&roof),
+                                candidate_name(&union),
+                                cells_name(&cells)
+                            );
+                            rectangle_reason("type 3", &extra)
+                        });
+                    }
+                }
+
+                // Type 4
+                for (x, y) in [(a, b), (b, a)] {
+                    if self.is_only_positions(&partition, x, &roof) {
+                        let mut step = rectangle_step();
+                        for pos in roof.iter() {
+                            step.eliminate(*pos, y);
+                        }
+                        recorder.apply(self, step, || {
+                            let extra = format!(", {} must hold {}", cells_name(&roof), x + 1);
+                            rectangle_reason("type 4", &extra)
+                        });
+                    }
+                }
+            }
+        }
+
+        // Type 6
+        if floor.len() == 2 && !is_peer(&floor[0], &floor[1]) {
+            let rows = [corners[0], corners[2]]
+                .map(|pos| get_sudoku_ruler_partition_map(&pos)[ROW_RULER_ID]);
+            let cols = [corners[0], corners[1]]
+                .map(|pos| get_sudoku_ruler_partition_map(&pos)[COL_RULER_ID]);
+            for x in [a, b] {
+                let is_x_wing = [rows, cols].iter().any(|lines| {
+                    lines
+                        .iter()
+                        .all(|line| self.is_only_positions(line, x, corners))
+                });
+                if is_x_wing {
+                    let mut step = rectangle_step();
+                    for pos in roof.iter() {
+                        step.eliminate(*pos, x);
+                    }
+                    recorder.apply(self, step, || {
+                        let extra = format!(", {} forms an X-Wing on the rectangle", x + 1);
+                        rectangle_reason("type 6", &extra)
+                    });
+                }
+            }
+        }
+
+        // Hidden
+        for (i, corner) in corners.iter().enumerate() {
+            if !floor.contains(corner) {
+                continue;
+            }
+            let opposite = corners[3 - i];
+            let partition_map = get_sudoku_ruler_partition_map(&opposite);
+            for (x, y) in [(a, b), (b, a)] {
+                if self.is_only_positions(&partition_map[ROW_RULER_ID], x, corners)
+                    && self.is_only_positions(&partition_map[COL_RULER_ID], x, corners)
+                {
+                    let mut step = rectangle_step();
+                    step.eliminate(opposite, y);
+                    recorder.apply(self, step, || {
+                        let extra = format!(
+                            ", {} in the row and column of {} lies within the rectangle",
+                            x + 1,
+                            cell_name(&opposite)
+                        );
+                        rectangle_reason("hidden", &extra)
+                    });
+                }
+            }
+        }
+    }
+
+    /// 每个分区的每个数值 均恰好有零或两个未确定位置可选
+    fn is_bivalue_universal_grave(&self) -> bool {
+        let mut is_bug = true;
+        each_sudoku_partition(|_, partition| {
+            for value_id in 0..SQUARE_OUTER_LEN {
+                let count = partition
+                    .iter()
+                    .filter(|(row, col)| {
+                        let can = self.can_matrix[*row][*col];
+                        can.count() > 1 && can.has(value_id)
+                    })
+                    .count();
+                is_bug &= count == 0 || count == 2;
+            }
+        });
+        is_bug
+    }
+
+    pub fn evolution_by_bug(&mut self) {
+        self.record_bug(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_bug(&mut self, recorder: &mut Recorder) {
+        let mut triple = Vec::new();
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                match self.can_matrix[row][col].count() {
+                    0..=2 => {}
+                    3 => triple.push((row, col)),
+                    _ => return,
+                }
+            }
+        }
+        if triple.len() != 1 {
+            return;
+        }
+        let (row, col) = triple[0];
+        let mut certain = Vec::new();
+        for value_id in self.can_matrix[row][col].iter() {
+            let mut shadow = *self;
+            shadow.can_matrix[row][col].remove(value_id);
+            if shadow.is_bivalue_universal_grave() {
+                certain.push(value_id);
+            }
+        }
+        if certain.len() == 1 {
+            let mut step = Step::new(Technique::Bug, vec![(row, col)], &certain);
+            step.place((row, col), certain[0]);
+            recorder.apply(self, step, || {
+                format!(
+                    "every other unsolved cell is bivalue, without {} at {} the solution is not unique",
+                    certain[0] + 1,
+                    cell_name(&(row, col))
+                )
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        fixture::{candidate_of, keep_only, line},
+        rulers::init,
+    };
+
+    use super::*;
+
+    fn rectangle(values: [&[usize]; 4]) -> CandidateMatrix {
+        let mut can = CandidateMatrix::new();
+        for (pos, values) in [(0, 0), (0, 4), (1, 0), (1, 4)].iter().zip(values) {
+            can.can_matrix[pos.0][pos.1] = candidate_of(values);
+        }
+        can
+    }
+
+    #[test]
+    fn test_unique_rectangle_type_1() {
+        init();
+
+        let mut can = rectangle([&[1, 2], &[1, 2], &[1, 2], &[1, 2, 5, 7]]);
+        can.evolution_by_unique_rectangle();
+        assert_eq!(can.can_matrix[1][4], candidate_of(&[5, 7]));
+    }
+
+    #[test]
+    fn test_unique_rectangle_type_2() {
+        init();
+
+        let mut can = rectangle([&[1, 2], &[1, 2], &[1, 2, 5], &[1, 2, 5]]);
+        can.evolution_by_unique_rectangle();
+        assert!(!can.can_matrix[1][7].has(4));
+        assert!(can.can_matrix[2][0].has(4));
+        assert_eq!(can.can_matrix[1][0], candidate_of(&[1, 2, 5]));
+    }
+
+    #[test]
+    fn test_unique_rectangle_type_3() {
+        init();
+
+        let mut can = rectangle([&[1, 2], &[1, 2], &[1, 2, 5], &[1, 2, 6]]);
+        can.can_matrix[1][7] = candidate_of(&[5, 6]);
+        can.evolution_by_unique_rectangle();
+        assert!(!can.can_matrix[1][8].has(4));
+        assert!(!can.can_matrix[1][8].has(5));
+        assert_eq!(can.can_matrix[1][0], candidate_of(&[1, 2, 5]));
+        assert_eq!(can.can_matrix[1][7], candidate_of(&[5, 6]));
+    }
+
+    #[test]
+    fn test_unique_rectangle_type_4() {
+        init();
+
+        let mut can = rectangle([&[1, 2], &[1, 2], &[1, 2, 5, 6], &[1, 2, 7]]);
+        keep_only(&mut can, 0, &line(1), &[(1, 0), (1, 4)]);
+        can.evolution_by_unique_rectangle();
+        assert_eq!(can.can_matrix[1][0], candidate_of(&[1, 5, 6]));
+        assert_eq!(can.can_matrix[1][4], candidate_of(&[1, 7]));
+    }
+
+    #[test]
+    fn test_unique_rectangle_type_5() {
+        init();
+
+        let mut can = rectangle([&[1, 2], &[1, 2, 5], &[1, 2, 5], &[1, 2]]);
+        can.evolution_by_unique_rectangle();
+        assert!(!can.can_matrix[0][1].has(4));
+        assert!(!can.can_matrix[1][5].has(4));
+        assert!(can.can_matrix[2][0].has(4));
+    }
+
+    #[test]
+    fn test_unique_rectangle_type_6() {
+        init();
+
+        let mut can = rectangle([&[1, 2], &[1, 2, 5], &[1, 2, 6], &[1, 2]]);
+        keep_only(&mut can, 0, &line(0), &[(0, 0), (0, 4)]);
+        keep_only(&mut can, 0, &line(1), &[(1, 0), (1, 4)]);
+        can.evolution_by_unique_rectangle();
+        assert_eq!(can.can_matrix[0][4], candidate_of(&[2, 5]));
+        assert_eq!(can.can_matrix[1][0], candidate_of(&[2, 6]));
+    }
+
+    #[test]
+    fn test_hidden_unique_rectangle() {
+        init();
+
+        let mut can = rectangle([&[1, 2], &[1, 2, 8], &[1, 2, 7], &[1, 2, 5, 6]]);
+        keep_only(&mut can, 0, &line(1), &[(1, 0), (1, 4)]);
+        for row in 2..9 {
+            can.can_matrix[row][4].remove(0);
+        }
+        can.evolution_by_unique_rectangle();
+        assert_eq!(can.can_matrix[1][4], candidate_of(&[1, 5, 6]));
+        assert_eq!(can.can_matrix[0][4], candidate_of(&[1, 2, 8]));
+    }
+
+    #[test]
+    fn test_bug_plus_one() {
+        init();
+
+        // 除去 (1, 4) 的 3 后为致命结构
+        let mut can = CandidateMatrix::new();
+        for row in 0..9 {
+            for col in 0..9 {
+                can.can_matrix[row][col] = candidate_of(&[9]);
+            }
+        }
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[0][4] = candidate_of(&[1, 2]);
+        can.can_matrix[1][0] = candidate_of(&[1, 2]);
+        can.can_matrix[1][4] = candidate_of(&[1, 2, 3]);
+        can.evolution_by_bug();
+        assert_eq!(can.can_matrix[1][4], candidate_of(&[3]));
+    }
+}
@@ -0,0 +1,245 @@
+//! 翼：枢纽与两个可见枢纽的双值钳子 三者候选值合计仅三个 两个钳子共有一个数值 z
+//! XY-Wing：枢纽 {x,y} 钳子 {x,z} {y,z} 无论枢纽为何值 总有一个钳子为 z
+//! XYZ-Wing：枢纽 {x,y,z} 钳子同上 枢纽或某个钳子必为 z
+//! -- 可将同时可见所有含 z 位置的其他位置标记 z 的黑名单
+//! W-Wing：两个互不可见的双值格 {x,y} 若 x 的某条强链两端分别可见这两个位置
+//! 则两个位置不能同时为 x 必有其一为 y -- 可将同时可见两个位置的其他位置标记 y 的黑名单
+
+use crate::sudoku::{entity::SQUARE_OUTER_LEN, rulers::Position};
+
+use super::{
+    candidate_name, cell_name, cells_name, is_peer, step::Recorder, CandidateMatrix, Step,
+    Technique,
+};
+
+impl CandidateMatrix {
+    pub fn evolution_by_wing(&mut self) {
+        self.record_wing(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_wing(&mut self, recorder: &mut Recorder) {
+        let mut bivalues: Vec<Position> = Vec::new();
+        let mut pivots: Vec<Position> = Vec::new();
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                match self.can_matrix[row][col].count() {
+                    2 => {
+                        bivalues.push((row, col));
+                        pivots.push((row, col));
+                    }
+                    3 => pivots.push((row, col)),
+                    _ => {}
+                }
+            }
+        }
+
+        for pivot in pivots.iter() {
+            let pincers: Vec<Position> = bivalues
+                .iter()
+                .filter(|pos| is_peer(pivot, pos))
+                .copied()
+                .collect();
+            for (i, first) in pincers.iter().enumerate() {
+                for second in pincers.iter().skip(i + 1) {
+                    if recorder.is_done() {
+                        return;
+                    }
+                    let pivot_can = self.can_matrix[pivot.0][pivot.1];
+                    let first_can = self.can_matrix[first.0][first.1];
+                    let second_can = self.can_matrix[second.0][second.1];
+                    let union = pivot_can.union(&first_can).union(&second_can);
+                    let common = first_can.intersection(&second_can);
+                    if union.count() != 3 || first_can == second_can {
+                        continue;
+                    }
+                    let Some(value) = common.only() else {
+                        continue;
+                    };
+                    let value_id = value - 1;
+                    // 含 z 的位置
+                    let mut wing = vec![*first, *second];
+                    if pivot_can.has(value_id) {
+                        wing.push(*pivot);
+                    }
+                    let value_ids: Vec<usize> = union.iter().collect();
+                    let mut step =
+                        Step::new(Technique::Wing, vec![*pivot, *first, *second], &value_ids);
+                    step.eliminate_common_peers(value_id, &wing);
+                    recorder.apply(self, step, || {
+                        format!(
+                            "pivot {} {} with pincers {} {} and {} {}, one of {} is {}",
+                            cell_name(pivot),
+                            candidate_name(&pivot_can),
+                            cell_name(first),
+                            candidate_name(&first_can),
+                            cell_name(second),
+                            candidate_name(&second_can),
+                            cells_name(&wing),
+                            value
+                        )
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn evolution_by_w_wing(&mut self) {
+        self.record_w_wing(&mut Recorder::silent());
+    }
+
+    pub(super) fn record_w_wing(&mut self, recorder: &mut Recorder) {
+        let mut bivalues: Vec<Position> = Vec::new();
+        for row in 0..SQUARE_OUTER_LEN {
+            for col in 0..SQUARE_OUTER_LEN {
+                if self.can_matrix[row][col].count() == 2 {
+                    bivalues.push((row, col));
+                }
+            }
+        }
+        let links: Vec<Vec<(Position, Position)>> = (0..SQUARE_OUTER_LEN)
+            .map(|value_id| self.strong_links(value_id))
+            .collect();
+
+        for (i, first) in bivalues.iter().enumerate() {
+            for second in bivalues.iter().skip(i + 1) {
+                if recorder.is_done() {
+                    return;
+                }
+                let can = self.can_matrix[first.0][first.1];
+                // 之前的排除可能已使其不再是双值格
+                if can.count() != 2
+                    || can != self.can_matrix[second.0][second.1]
+                    || is_peer(first, second)
+                {
+                    continue;
+                }
+                let value_ids: Vec<usize> = can.iter().collect();
+                for (x, y) in [(value_ids[0], value_ids[1]), (value_ids[1], value_ids[0])] {
+                    let link = links[x]
+                        .iter()
+                        .flat_map(|(a, b)| [(*a, *b), (*b, *a)])
+                        .find(|(a, b)| {
+                            a != second && b != first && is_peer(a, first) && is_peer(b, second)
+                        });
+                    let Some((a, b)) = link else {
+                        continue;
+                    };
+                    let mut step =
+                        Step::new(Technique::WWing, vec![*first, *second, a, b], &[x, y]);
+                    step.eliminate_common_peers(y, &[*first, *second]);
+                    recorder.apply(self, step, || format!(
+                            "{} and {} hold only {}, the strong link of {} {}-{} makes one of them {}",
+                            cell_name(first),
+                            cell_name(second),
+                            candidate_name(&can),
+                            x + 1,
+                            cell_name(&a),
+                            cell_name(&b),
+                            y + 1
+                        ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{fixture::candidate_of, rulers::init};
+
+    use super::*;
+
+    #[test]
+    fn test_xy_wing() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[0][5] = candidate_of(&[1, 3]);
+        can.can_matrix[4][0] = candidate_of(&[2, 3]);
+        can.evolution_by_wing();
+        assert!(!can.can_matrix[4][5].has(2));
+        // 仅可见一个钳子
+        assert!(can.can_matrix[4][4].has(2));
+        assert!(can.can_matrix[1][5].has(2));
+        // 钳子本身
+        assert_eq!(can.can_matrix[0][5], candidate_of(&[1, 3]));
+        assert_eq!(can.can_matrix[4][0], candidate_of(&[2, 3]));
+    }
+
+    #[test]
+    fn test_wing_step() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[0][5] = candidate_of(&[1, 3]);
+        can.can_matrix[4][0] = candidate_of(&[2, 3]);
+        let step = can.evolution_by_technique(Technique::Wing).unwrap();
+        // 枢纽与两个钳子
+        assert_eq!(step.cells, vec![(0, 0), (0, 5), (4, 0)]);
+        assert_eq!(
+            step.reason,
+            "pivot r1c1 {1,2} with pincers r1c6 {1,3} and r5c1 {2,3}, one of r1c6, r5c1 is 3"
+        );
+        assert_eq!(step.eliminations, vec![((4, 5), 3)]);
+    }
+
+    #[test]
+    fn test_xyz_wing() {
+        init();
+
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2, 3]);
+        can.can_matrix[0][5] = candidate_of(&[1, 3]);
+        can.can_matrix[1][1] = candidate_of(&[2, 3]);
+        can.evolution_by_wing();
+        assert!(!can.can_matrix[0][1].has(2));
+        assert!(!can.can_matrix[0][2].has(2));
+        // 不可见枢纽所在九宫格的钳子
+        assert!(can.can_matrix[0][4].has(2));
+        // 不可见 (0, 5)
+        assert!(can.can_matrix[2][2].has(2));
+        assert_eq!(can.can_matrix[0][0], candidate_of(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_w_wing() {
+        init();
+
+        // 双值格 (0, 0) (4, 8) 均为 {1, 2} 1 在第 4 列仅能位于 0、4 行
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[4][8] = candidate_of(&[1, 2]);
+        for row in 0..9 {
+            if row != 0 && row != 4 {
+                can.can_matrix[row][4].remove(0);
+            }
+        }
+        can.evolution_by_w_wing();
+        assert!(!can.can_matrix[0][8].has(1));
+        assert!(!can.can_matrix[4][0].has(1));
+        assert!(can.can_matrix[0][8].has(0));
+        assert!(can.can_matrix[4][4].has(1));
+    }
+
+    #[test]
+    fn test_w_wing_reduces_bivalue() {
+        init();
+
+        // (0, 8) (4, 0) 均为 {2, 3} 被第一组排除 2 后均仅剩 3
+        let mut can = CandidateMatrix::new();
+        can.can_matrix[0][0] = candidate_of(&[1, 2]);
+        can.can_matrix[4][8] = candidate_of(&[1, 2]);
+        can.can_matrix[0][8] = candidate_of(&[2, 3]);
+        can.can_matrix[4][0] = candidate_of(&[2, 3]);
+        for row in 0..9 {
+            if row != 0 && row != 4 {
+                can.can_matrix[row][4].remove(0);
+            }
+        }
+        can.evolution_by_w_wing();
+        assert_eq!(can.can_matrix[0][8], candidate_of(&[3]));
+        assert_eq!(can.can_matrix[4][0], candidate_of(&[3]));
+    }
+}
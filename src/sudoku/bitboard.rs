@@ -0,0 +1,452 @@
+//! 位棋盘：面向批量校验的高吞吐求解器
+//! 每个数值保存一个 81 位的位置掩码 以位运算完成唯一余数、排除法与区块排除的推演
+//! 只检查上次推演后有变化的位置与数值 候选值个数按位累加 不逐个位置计数
+//! 推演无进展时选择候选值最少的位置猜测 棋盘按值复制 回溯无需撤销
+//! 分区与互相可见的位置直接取自 RulerLoop 规则变化时无需修改
+
+use std::sync::OnceLock;
+
+use super::{
+    algorithm::CandidateMatrix,
+    entity::{SudokuMatrixValue, SQUARE_OUTER_LEN},
+    guess::Solver,
+    rulers::{get_sudoku_ruler_loop, RULER_COUNT},
+};
+
+/// 每个位置对应一个比特 以 row * SQUARE_OUTER_LEN + col 为序号
+type PositionMask = u128;
+
+/// 位置总数
+const CELL_COUNT: usize = SQUARE_OUTER_LEN * SQUARE_OUTER_LEN;
+/// 分区总数
+const PARTITION_COUNT: usize = RULER_COUNT * SQUARE_OUTER_LEN;
+const ALL_CELLS: PositionMask = (1 << CELL_COUNT) - 1;
+const ALL_VALUES: u16 = (1 << SQUARE_OUTER_LEN) - 1;
+/// 按位累加候选值个数所需的位数 足以表示 0..=9
+const COUNT_BITS: usize = 4;
+
+struct Tables {
+    /// 各分区包含的位置
+    partitions: [PositionMask; PARTITION_COUNT],
+    /// 各位置互相可见的其他位置
+    peers: [PositionMask; CELL_COUNT],
+    /// 相交至少两个位置的两个分区 (交集, 第一个分区的其余位置, 第二个分区的其余位置)
+    intersections: Vec<(PositionMask, PositionMask, PositionMask)>,
+}
+
+/// 规则初始化后不再变化 首次使用时生成
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = Tables {
+            partitions: [0; PARTITION_COUNT],
+            peers: [0; CELL_COUNT],
+            intersections: Vec::new(),
+        };
+        for (ruler_id, ruler) in get_sudoku_ruler_loop().iter().enumerate() {
+            for (partition_id, partition) in ruler.partitions.iter().enumerate() {
+                let mask = partition.iter().fold(0, |mask, (row, col)| {
+                    mask | bit_of(row * SQUARE_OUTER_LEN + col)
+                });
+                tables.partitions[ruler_id * SQUARE_OUTER_LEN + partition_id] = mask;
+            }
+        }
+        for (cell, peers) in tables.peers.iter_mut().enumerate() {
+            for partition in tables.partitions.iter() {
+                if partition & bit_of(cell) != 0 {
+                    *peers |= partition;
+                }
+            }
+            *peers &= !bit_of(cell);
+        }
+        for (i, first) in tables.partitions.iter().enumerate() {
+            for second in tables.partitions.iter().skip(i + 1) {
+                let common = first & second;
+                if common.count_ones() > 1 {
+                    tables
+                        .intersections
+                        .push((common, first & !common, second & !common));
+                }
+            }
+        }
+        tables
+    })
+}
+
+fn bit_of(cell: usize) -> PositionMask {
+    1 << cell
+}
+
+/// 依次取出最低位的位置
+fn cells_of(mut mask: PositionMask) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let cell = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(cell)
+    })
+}
+
+#[derive(Copy, Clone)]
+struct Board {
+    /// value_id -> 该数值仍可选的未确定位置
+    candidates: [PositionMask; SQUARE_OUTER_LEN],
+    /// value_id -> 已确定为该数值的位置
+    placed: [PositionMask; SQUARE_OUTER_LEN],
+    /// 尚未确定的位置
+    unsolved: PositionMask,
+    /// 上次检查唯一余数后 失去候选值的位置
+    touched: PositionMask,
+    /// 上次检查排除法后 可选位置有变化的数值 第 value_id 位
+    dirty: u16,
+}
+
+impl From<CandidateMatrix> for Board {
+    fn from(value: CandidateMatrix) -> Self {
+        let mut board = Board {
+            candidates: [0; SQUARE_OUTER_LEN],
+            placed: [0; SQUARE_OUTER_LEN],
+            unsolved: ALL_CELLS,
+            touched: ALL_CELLS,
+            dirty: ALL_VALUES,
+        };
+        for (row, ll) in value.can_matrix.iter().enumerate() {
+            for (col, can) in ll.iter().enumerate() {
+                for value_id in can.iter() {
+                    board.candidates[value_id] |= bit_of(row * SQUARE_OUTER_LEN + col);
+                }
+            }
+        }
+        board
+    }
+}
+
+impl From<Board> for SudokuMatrixValue {
+    fn from(value: Board) -> Self {
+        let mut matrix = SudokuMatrixValue::new();
+        for (value_id, placed) in value.placed.iter().enumerate() {
+            for cell in cells_of(*placed) {
+                matrix.matrix[cell / SQUARE_OUTER_LEN][cell % SQUARE_OUTER_LEN] = value_id + 1;
+            }
+        }
+        matrix
+    }
+}
+
+impl Board {
+    /// 确定该位置的值 并从互相可见的位置中排除 冲突返回 false
+    /// 记录失去候选值的位置与可选位置变化的数值 推演时只检查这些
+    fn place(&mut self, tables: &Tables, cell: usize, value_id: usize) -> bool {
+        let bit = bit_of(cell);
+        if self.candidates[value_id] & bit == 0 || self.placed[value_id] & tables.peers[cell] != 0 {
+            return false;
+        }
+        self.placed[value_id] |= bit;
+        self.unsolved &= !bit;
+        for (other_id, candidates) in self.candidates.iter_mut().enumerate() {
+            if *candidates & bit != 0 {
+                *candidates &= !bit;
+                self.dirty |= 1 << other_id;
+            }
+        }
+        let removed = self.candidates[value_id] & tables.peers[cell];
+        self.candidates[value_id] &= !removed;
+        self.touched |= removed;
+        true
+    }
+
+    /// 唯一余数与排除法推演至无进展 出现矛盾返回 false
+    fn propagate(&mut self, tables: &Tables) -> bool {
+        loop {
+            // 仅失去候选值的位置可能成为唯一余数或无候选值
+            let cells = self.touched & self.unsolved;
+            self.touched = 0;
+            if cells != 0 {
+                // 逐位累加 得到至少一个 与至少两个候选值的位置
+                let (mut once, mut twice) = (0, 0);
+                for candidates in self.candidates.iter() {
+                    let candidates = candidates & cells;
+                    twice |= once & candidates;
+                    once |= candidates;
+                }
+                if cells & !once != 0 {
+                    return false;
+                }
+                let singles = once & !twice;
+                for cell in cells_of(singles) {
+                    // 同一批中先确定的位置可能已排除此处仅有的候选值
+                    let Some(value_id) = (0..SQUARE_OUTER_LEN)
+                        .find(|value_id| self.candidates[*value_id] & bit_of(cell) != 0)
+                    else {
+                        return false;
+                    };
+                    if !self.place(tables, cell, value_id) {
+                        return false;
+                    }
+                }
+                // 排除法的代价较高 唯一余数无进展时再使用
+                if singles != 0 {
+                    continue;
+                }
+            }
+
+            // 仅可选位置有变化的数值可能出现新的排除法结论
+            if self.dirty == 0 {
+                if self.lock(tables) {
+                    continue;
+                }
+                return true;
+            }
+            while self.dirty != 0 {
+                let value_id = self.dirty.trailing_zeros() as usize;
+                self.dirty &= self.dirty - 1;
+                for partition in tables.partitions.iter() {
+                    if self.placed[value_id] & partition != 0 {
+                        continue;
+                    }
+                    let mask = self.candidates[value_id] & partition;
+                    if mask == 0 {
+                        return false;
+                    }
+                    if mask & (mask - 1) == 0
+                        && !self.place(tables, mask.trailing_zeros() as usize, value_id)
+                    {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    /// 两个分区相交 某一数值在一个分区内的位置均在交集内 可排除另一分区其余位置
+    fn lock(&mut self, tables: &Tables) -> bool {
+        let mut changed = false;
+        for value_id in 0..SQUARE_OUTER_LEN {
+            let candidates = self.candidates[value_id];
+            let mut removed = 0;
+            for (common, first_rest, second_rest) in tables.intersections.iter() {
+                if candidates & common == 0 {
+                    continue;
+                }
+                if candidates & first_rest == 0 {
+                    removed |= candidates & second_rest;
+                } else if candidates & second_rest == 0 {
+                    removed |= candidates & first_rest;
+                }
+            }
+            if removed != 0 {
+                self.candidates[value_id] &= !removed;
+                self.touched |= removed;
+                self.dirty |= 1 << value_id;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// 候选值最少的未确定位置
+    fn most_constrained(&self) -> Option<usize> {
+        // 逐位累加 planes[i] 为各位置候选值个数的第 i 位
+        let mut planes = [0; COUNT_BITS];
+        for candidates in self.candidates.iter() {
+            let mut carry = *candidates;
+            for plane in planes.iter_mut() {
+                let next = *plane & carry;
+                *plane ^= carry;
+                carry = next;
+            }
+        }
+        // 推演后不存在仅一个候选值的位置 从两个开始
+        (2..=SQUARE_OUTER_LEN).find_map(|count| {
+            let cells = planes
+                .iter()
+                .enumerate()
+                .fold(self.unsolved, |cells, (i, plane)| {
+                    if count & (1 << i) != 0 {
+                        cells & plane
+                    } else {
+                        cells & !plane
+                    }
+                });
+            cells_of(cells).next()
+        })
+    }
+}
+
+pub struct BitboardSolver {
+    board: Board,
+    all_possible: Vec<SudokuMatrixValue>,
+}
+
+impl From<CandidateMatrix> for BitboardSolver {
+    fn from(value: CandidateMatrix) -> Self {
+        BitboardSolver {
+            board: value.into(),
+            all_possible: Vec::new(),
+        }
+    }
+}
+
+impl BitboardSolver {
+    fn search(&mut self, tables: &Tables, mut board: Board, limit: usize) {
+        if self.all_possible.len() >= limit || !board.propagate(tables) {
+            return;
+        }
+        let Some(cell) = board.most_constrained() else {
+            self.all_possible.push(board.into());
+            return;
+        };
+        for value_id in 0..SQUARE_OUTER_LEN {
+            if board.candidates[value_id] & bit_of(cell) == 0 {
+                continue;
+            }
+            let mut next = board;
+            if next.place(tables, cell, value_id) {
+                self.search(tables, next, limit);
+            }
+            if self.all_possible.len() >= limit {
+                break;
+            }
+        }
+    }
+}
+
+impl Solver for BitboardSolver {
+    fn count_solutions(&mut self, limit: usize) -> usize {
+        self.all_possible.clear();
+        self.search(tables(), self.board, limit);
+        self.all_possible.len()
+    }
+
+    fn get_all_possible_sudoku(&self) -> &Vec<SudokuMatrixValue> {
+        &self.all_possible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        entity::SUDOKU_UNKNOWN,
+        fixture::{parse, HARD_SUDOKU},
+        guess::SudokuSolver,
+        rulers::init,
+    };
+
+    use super::*;
+
+    /// 17 个已知数的数独 最后一题为 Norvig 的 hard1
+    const SEVENTEEN_CLUES: [&str; 6] = [
+        "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+        "000000010400000000020000000000050604008000300001090000300400200050100000000807000",
+        "000000012000035000000600070700000300000400800100000000000120000080000040050000600",
+        "000000012003600000000007000410020000000500300700000600280000040000300500000000000",
+        "000000012008030000000000040120500000000004700060000000507000300000620000000100000",
+        "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......",
+    ];
+
+    /// 单线程每秒至少校验两万道题 需以 release 运行：
+    /// cargo test --release -- --ignored test_throughput
+    #[test]
+    #[ignore = "throughput check, run in release mode"]
+    fn test_throughput() {
+        init();
+
+        let puzzles: Vec<CandidateMatrix> = SEVENTEEN_CLUES
+            .iter()
+            .map(|sudoku| CandidateMatrix::from(parse(sudoku)))
+            .collect();
+        let rounds = 5000;
+        let start = std::time::Instant::now();
+        for _ in 0..rounds {
+            for can in puzzles.iter() {
+                assert!(BitboardSolver::from(*can).is_unique());
+            }
+        }
+        let per_second = (rounds * puzzles.len()) as f64 / start.elapsed().as_secs_f64();
+        assert!(per_second >= 20_000.0, "{per_second:.0} puzzles per second");
+    }
+
+    #[test]
+    fn test_seventeen_clues() {
+        init();
+
+        for sudoku in SEVENTEEN_CLUES {
+            let can = CandidateMatrix::from(parse(sudoku));
+            let mut bitboard = BitboardSolver::from(can);
+            let mut naive = SudokuSolver::from(can);
+            assert_eq!(bitboard.count_solutions(2), 1);
+            assert_eq!(naive.count_solutions(2), 1);
+            assert_eq!(
+                bitboard.get_all_possible_sudoku(),
+                naive.get_all_possible_sudoku()
+            );
+        }
+    }
+
+    #[test]
+    fn test_hard_sudoku() {
+        init();
+
+        for (sudoku, solution) in HARD_SUDOKU {
+            let mut solver = BitboardSolver::from(CandidateMatrix::from(parse(sudoku)));
+            assert!(solver.is_unique());
+            assert_eq!(solver.count_solutions(10), 1);
+            assert_eq!(solver.get_all_possible_sudoku(), &vec![parse(solution)]);
+        }
+    }
+
+    #[test]
+    fn test_same_as_sudoku_solver() {
+        init();
+
+        // 终盘中 (0,0) (0,2) (7,0) (7,2) 为 1 2 / 2 1 的矩形 去掉后可交换
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        for (row, col) in [(0, 0), (0, 2), (7, 0), (7, 2)] {
+            sudoku.matrix[row][col] = SUDOKU_UNKNOWN;
+        }
+        // 再去掉一些位置 使解的个数更多
+        for col in 3..SQUARE_OUTER_LEN {
+            sudoku.matrix[4][col] = SUDOKU_UNKNOWN;
+        }
+        let can = CandidateMatrix::from(sudoku);
+        let mut bitboard = BitboardSolver::from(can);
+        let mut naive = SudokuSolver::from(can);
+        let count = naive.count_solutions(usize::MAX);
+        assert!(count >= 2);
+        assert_eq!(bitboard.count_solutions(usize::MAX), count);
+        for solution in naive.get_all_possible_sudoku() {
+            assert!(bitboard.get_all_possible_sudoku().contains(solution));
+        }
+    }
+
+    #[test]
+    fn test_count_solutions_limit() {
+        init();
+
+        let mut solver = BitboardSolver::from(CandidateMatrix::new());
+        assert_eq!(solver.count_solutions(5), 5);
+        assert!(!solver.is_unique());
+        assert_eq!(solver.count_solutions(0), 0);
+        assert_eq!(solver.count_solutions(3), 3);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        init();
+
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        sudoku.matrix[0][0] = SUDOKU_UNKNOWN;
+        sudoku.matrix[0][1] = 1;
+        let mut solver = BitboardSolver::from(CandidateMatrix::from(sudoku));
+        assert_eq!(solver.count_solutions(10), 0);
+
+        // 已知数本身冲突
+        let mut sudoku = SudokuMatrixValue::new();
+        sudoku.matrix[0][0] = 5;
+        sudoku.matrix[8][0] = 5;
+        let mut solver = BitboardSolver::from(CandidateMatrix::from(sudoku));
+        assert_eq!(solver.count_solutions(10), 0);
+    }
+}
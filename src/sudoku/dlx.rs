@@ -0,0 +1,286 @@
+//! 舞蹈链（Algorithm X）：将数独转化为精确覆盖问题
+//! 每个候选值为一行 覆盖一个位置约束 以及各规则中所在分区的该数值约束
+//! 约束直接取自 RulerLoop 规则变化时无需修改
+
+use super::{
+    algorithm::CandidateMatrix,
+    entity::{SudokuMatrixValue, SudokuValueType, SQUARE_OUTER_LEN},
+    guess::Solver,
+    rulers::{get_sudoku_ruler_loop, Position, RULER_COUNT},
+};
+
+/// 表头节点
+const ROOT: usize = 0;
+/// 位置约束的个数
+const CELL_CONSTRAINTS: usize = SQUARE_OUTER_LEN * SQUARE_OUTER_LEN;
+/// 约束列的总数 位置约束之后依次是各规则各分区各数值的约束
+const COLUMN_COUNT: usize = CELL_CONSTRAINTS + RULER_COUNT * SQUARE_OUTER_LEN * SQUARE_OUTER_LEN;
+
+pub struct DlxSolver {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// 节点所在的约束列
+    column: Vec<usize>,
+    /// 节点所在的行 即候选值的编号
+    row_of: Vec<usize>,
+    /// 约束列中剩余的节点数
+    size: Vec<usize>,
+    /// 行对应的位置与数值
+    rows: Vec<(Position, SudokuValueType)>,
+    partial: Vec<usize>,
+    all_possible: Vec<SudokuMatrixValue>,
+}
+
+impl From<CandidateMatrix> for DlxSolver {
+    fn from(value: CandidateMatrix) -> Self {
+        let mut solver = DlxSolver {
+            left: Vec::new(),
+            right: Vec::new(),
+            up: Vec::new(),
+            down: Vec::new(),
+            column: Vec::new(),
+            row_of: Vec::new(),
+            size: vec![0; COLUMN_COUNT + 1],
+            rows: Vec::new(),
+            partial: Vec::new(),
+            all_possible: Vec::new(),
+        };
+        // 表头与约束列首尾相连
+        for col in 0..=COLUMN_COUNT {
+            solver
+                .left
+                .push(if col == ROOT { COLUMN_COUNT } else { col - 1 });
+            solver
+                .right
+                .push(if col == COLUMN_COUNT { ROOT } else { col + 1 });
+            solver.up.push(col);
+            solver.down.push(col);
+            solver.column.push(col);
+            solver.row_of.push(usize::MAX);
+        }
+
+        // 位置 -> 各规则中所在分区的编号
+        let mut partition_ids = [[[0; RULER_COUNT]; SQUARE_OUTER_LEN]; SQUARE_OUTER_LEN];
+        for (ruler_id, ruler) in get_sudoku_ruler_loop().iter().enumerate() {
+            for (partition_id, partition) in ruler.partitions.iter().enumerate() {
+                for (row, col) in partition.iter() {
+                    partition_ids[*row][*col][ruler_id] = partition_id;
+                }
+            }
+        }
+
+        for (row, ll) in value.can_matrix.iter().enumerate() {
+            for (col, can) in ll.iter().enumerate() {
+                for value_id in 0..SQUARE_OUTER_LEN {
+                    if !can.has(value_id) {
+                        continue;
+                    }
+                    let mut columns = vec![1 + row * SQUARE_OUTER_LEN + col];
+                    for (ruler_id, partition_id) in partition_ids[row][col].iter().enumerate() {
+                        columns.push(
+                            1 + CELL_CONSTRAINTS
+                                + (ruler_id * SQUARE_OUTER_LEN + partition_id) * SQUARE_OUTER_LEN
+                                + value_id,
+                        );
+                    }
+                    solver.add_row(((row, col), value_id + 1), &columns);
+                }
+            }
+        }
+        solver
+    }
+}
+
+impl DlxSolver {
+    fn add_row(&mut self, placement: (Position, SudokuValueType), columns: &[usize]) {
+        let row_id = self.rows.len();
+        self.rows.push(placement);
+        let first = self.left.len();
+        for (i, col) in columns.iter().enumerate() {
+            let node = first + i;
+            self.left.push(if i == 0 {
+                first + columns.len() - 1
+            } else {
+                node - 1
+            });
+            self.right.push(if i == columns.len() - 1 {
+                first
+            } else {
+                node + 1
+            });
+            // 插入到约束列的末尾
+            self.up.push(self.up[*col]);
+            self.down.push(*col);
+            let last = self.up[*col];
+            self.down[last] = node;
+            self.up[*col] = node;
+            self.column.push(*col);
+            self.row_of.push(row_id);
+            self.size[*col] += 1;
+        }
+    }
+
+    fn cover(&mut self, col: usize) {
+        self.right[self.left[col]] = self.right[col];
+        self.left[self.right[col]] = self.left[col];
+        let mut i = self.down[col];
+        while i != col {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, col: usize) {
+        let mut i = self.up[col];
+        while i != col {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[col]] = col;
+        self.left[self.right[col]] = col;
+    }
+
+    fn search(&mut self, limit: usize) {
+        if self.all_possible.len() >= limit {
+            return;
+        }
+        if self.right[ROOT] == ROOT {
+            let mut matrix = SudokuMatrixValue::new();
+            for node in self.partial.iter() {
+                let ((row, col), value) = self.rows[self.row_of[*node]];
+                matrix.matrix[row][col] = value;
+            }
+            self.all_possible.push(matrix);
+            return;
+        }
+
+        // 选择剩余节点最少的约束列
+        let mut col = self.right[ROOT];
+        let mut chosen = col;
+        while col != ROOT {
+            if self.size[col] < self.size[chosen] {
+                chosen = col;
+            }
+            col = self.right[col];
+        }
+        if self.size[chosen] == 0 {
+            return;
+        }
+
+        self.cover(chosen);
+        let mut node = self.down[chosen];
+        while node != chosen {
+            self.partial.push(node);
+            let mut j = self.right[node];
+            while j != node {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+            self.search(limit);
+            let mut j = self.left[node];
+            while j != node {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            self.partial.pop();
+            if self.all_possible.len() >= limit {
+                break;
+            }
+            node = self.down[node];
+        }
+        self.uncover(chosen);
+    }
+}
+
+impl Solver for DlxSolver {
+    fn count_solutions(&mut self, limit: usize) -> usize {
+        self.all_possible.clear();
+        self.search(limit);
+        self.all_possible.len()
+    }
+
+    fn get_all_possible_sudoku(&self) -> &Vec<SudokuMatrixValue> {
+        &self.all_possible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        entity::SUDOKU_UNKNOWN,
+        fixture::{parse, HARD_SUDOKU},
+        guess::SudokuSolver,
+        rulers::init,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_hard_sudoku() {
+        init();
+
+        for (sudoku, solution) in HARD_SUDOKU {
+            let mut solver = DlxSolver::from(CandidateMatrix::from(parse(sudoku)));
+            assert!(solver.is_unique());
+            assert_eq!(solver.count_solutions(10), 1);
+            assert_eq!(solver.get_all_possible_sudoku(), &vec![parse(solution)]);
+        }
+    }
+
+    #[test]
+    fn test_same_as_sudoku_solver() {
+        init();
+
+        // 终盘中 (0,0) (0,2) (7,0) (7,2) 为 1 2 / 2 1 的矩形 去掉后可交换
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        for (row, col) in [(0, 0), (0, 2), (7, 0), (7, 2)] {
+            sudoku.matrix[row][col] = SUDOKU_UNKNOWN;
+        }
+        let can = CandidateMatrix::from(sudoku);
+        let mut dlx = DlxSolver::from(can);
+        let mut naive = SudokuSolver::from(can);
+        assert_eq!(dlx.count_solutions(10), 2);
+        assert_eq!(naive.count_solutions(10), 2);
+        for solution in naive.get_all_possible_sudoku() {
+            assert!(dlx.get_all_possible_sudoku().contains(solution));
+        }
+    }
+
+    #[test]
+    fn test_count_solutions_limit() {
+        init();
+
+        let mut solver = DlxSolver::from(CandidateMatrix::new());
+        assert_eq!(solver.count_solutions(5), 5);
+        assert!(!solver.is_unique());
+        assert_eq!(solver.count_solutions(0), 0);
+        // 每次枚举后链表均已复原
+        assert_eq!(solver.count_solutions(3), 3);
+        assert_eq!(solver.get_all_possible_sudoku().len(), 3);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        init();
+
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        sudoku.matrix[0][0] = SUDOKU_UNKNOWN;
+        sudoku.matrix[0][1] = 1;
+        let mut solver = DlxSolver::from(CandidateMatrix::from(sudoku));
+        assert_eq!(solver.count_solutions(10), 0);
+    }
+}
@@ -10,10 +10,7 @@ pub fn new_sudoku_matrix<T: Copy>(init_value: T) -> SudokuMatrix<T> {
 pub type SudokuValueType = usize;
 pub const SUDOKU_UNKNOWN: SudokuValueType = 0;
 pub fn is_sudoku_value(value: SudokuValueType) -> bool {
-    match value {
-        1..=9 => true,
-        _ => false,
-    }
+    matches!(value, 1..=9)
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -39,3 +36,9 @@ impl SudokuMatrixValue {
         None
     }
 }
+
+impl Default for SudokuMatrixValue {
+    fn default() -> Self {
+        SudokuMatrixValue::new()
+    }
+}
@@ -0,0 +1,145 @@
+//! 测试用数独及其唯一解
+
+use super::{
+    algorithm::{Candidate, CandidateMatrix},
+    entity::{SudokuMatrixValue, SudokuValueType, SQUARE_INNER_LEN, SQUARE_OUTER_LEN},
+    rulers::{init, Position},
+};
+
+/// (题目, 答案) 前两题需要假设排除或猜测 第三题仅用唯一位置与唯一候选值即可完成
+pub const HARD_SUDOKU: [(&str, &str); 3] = [
+    (
+        "1....7.9..3..2...8..96..5....53..9...1..8...26....4...3......1..4......7..7...3..",
+        "162857493534129678789643521475312986913586742628794135356478219241935867897261354",
+    ),
+    (
+        "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        "812753649943682175675491283154237896369845721287169534521974368438526917796318452",
+    ),
+    (
+        "..............3.85..1.2.......5.7.....4...1...9.......5......73..2.1........4...9",
+        "987654321246173985351928746128537694634892157795461832519286473472319568863745219",
+    ),
+];
+
+/// (题目, 答案) 基础技巧无进展后 需要鱼、翼、强链、染色、链、待定数组等技巧
+pub const PATTERN_SUDOKU: [(&str, &str); 2] = [
+    (
+        "...3..4.......7.5...3.956.1.4...1.9..7...9..83......25..9..8....37........56.....",
+        "958316472614827953723495681842531796571269348396784125269148537137952864485673219",
+    ),
+    (
+        "1.....3.2..6..8....4.....1..5...794.27.6.......1.......9...5.....79..5......264..",
+        "189574362326198754745362819853217946274689135961453287492835671637941528518726493",
+    ),
+];
+
+pub fn candidate_of(values: &[SudokuValueType]) -> Candidate {
+    let mut can = Candidate::new_none();
+    for value in values {
+        can.insert(value - 1);
+    }
+    can
+}
+
+/// 仅保留给定位置的该候选值
+pub fn keep_only(
+    can: &mut CandidateMatrix,
+    value_id: usize,
+    positions: &[Position],
+    keep: &[Position],
+) {
+    for pos in positions {
+        if !keep.contains(pos) {
+            can.can_matrix[pos.0][pos.1].remove(value_id);
+        }
+    }
+}
+
+pub fn line(row: usize) -> Vec<Position> {
+    (0..SQUARE_OUTER_LEN).map(|col| (row, col)).collect()
+}
+
+pub fn column(col: usize) -> Vec<Position> {
+    (0..SQUARE_OUTER_LEN).map(|row| (row, col)).collect()
+}
+
+/// 以 (row, col) 为左上角的九宫格
+pub fn square(row: usize, col: usize) -> Vec<Position> {
+    let mut positions = Vec::new();
+    for r in row..row + SQUARE_INNER_LEN {
+        for c in col..col + SQUARE_INNER_LEN {
+            positions.push((r, c));
+        }
+    }
+    positions
+}
+
+pub fn parse(s: &str) -> SudokuMatrixValue {
+    let mut matrix = SudokuMatrixValue::new();
+    for (i, c) in s.chars().enumerate() {
+        matrix.matrix[i / SQUARE_OUTER_LEN][i % SQUARE_OUTER_LEN] =
+            c.to_digit(10).unwrap_or(0) as usize;
+    }
+    matrix
+}
+
+/// 所有排除都必须保留答案中的数值
+pub fn assert_keeps_solution(can: &CandidateMatrix, solution: &str) {
+    let solution = parse(solution);
+    for row in 0..SQUARE_OUTER_LEN {
+        for col in 0..SQUARE_OUTER_LEN {
+            let value = solution.matrix[row][col];
+            assert!(
+                can.can_matrix[row][col].has(value - 1),
+                "({row}, {col}) lost {value}"
+            );
+        }
+    }
+}
+
+/// 基础技巧无进展时应用某一技巧 反复直到无变化 每一步都必须保留答案
+/// 该技巧须在这些数独上至少排除一个候选值 否则测试没有意义
+pub fn assert_evolution_keeps_solution<F>(mut evolution: F)
+where
+    F: FnMut(&mut CandidateMatrix),
+{
+    init();
+
+    let mut removed = 0;
+    for (sudoku, solution) in HARD_SUDOKU.iter().chain(PATTERN_SUDOKU.iter()) {
+        let mut can = CandidateMatrix::from(parse(sudoku));
+        loop {
+            loop {
+                let origin = can;
+                can.evolution();
+                can.evolution_by_position_mutex();
+                can.evolution_by_value_mutex();
+                can.evolution_by_check_position();
+                if origin == can {
+                    break;
+                }
+            }
+            let origin = can;
+            evolution(&mut can);
+            assert_keeps_solution(&can, solution);
+            if origin == can {
+                break;
+            }
+            removed += count_removed(&origin, &can);
+        }
+    }
+    assert!(removed > 0, "the technique never fires");
+}
+
+fn count_removed(before: &CandidateMatrix, after: &CandidateMatrix) -> usize {
+    let mut count = 0;
+    for row in 0..SQUARE_OUTER_LEN {
+        for col in 0..SQUARE_OUTER_LEN {
+            count += before.can_matrix[row][col]
+                .difference(&after.can_matrix[row][col])
+                .count();
+        }
+    }
+    count
+}
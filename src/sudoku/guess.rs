@@ -1,60 +1,183 @@
 use super::{
-    algorithm::CandidateMatrix,
-    entity::{
-        is_sudoku_value, SudokuMatrixValue, SudokuValueType, SQUARE_OUTER_LEN, SUDOKU_UNKNOWN,
-    },
-    rulers::{get_sudoku_ruler_partition_map, Position},
+    algorithm::{Candidate, CandidateMatrix},
+    entity::{SudokuMatrixValue, SQUARE_OUTER_LEN},
 };
 
-fn is_valid(matrix: &SudokuMatrixValue, pos: &Position, num: SudokuValueType) -> bool {
-    if !is_sudoku_value(num) {
-        return false;
-    }
-
-    let mut valided = true;
-    let partition_list = get_sudoku_ruler_partition_map(pos);
-    for partition in partition_list {
-        for (row, col) in partition {
-            valided &= num != matrix.matrix[row][col];
-        }
-    }
-    valided
-}
-
 pub struct SudokuSolver {
     candi: CandidateMatrix,
-    current: SudokuMatrixValue,
     all_possible: Vec<SudokuMatrixValue>,
 }
 
 impl From<CandidateMatrix> for SudokuSolver {
     fn from(value: CandidateMatrix) -> Self {
-        let origin = SudokuMatrixValue::from(value);
-        return SudokuSolver {
+        SudokuSolver {
             candi: value,
-            current: origin,
             all_possible: Vec::new(),
-        };
+        }
+    }
+}
+
+/// 求解器的公共接口 不同的搜索实现可以相互替换
+pub trait Solver: From<CandidateMatrix> {
+    /// 枚举至多 limit 个解 数量足够后立即停止 返回找到的解的个数
+    fn count_solutions(&mut self, limit: usize) -> usize;
+
+    /// 最近一次枚举找到的解
+    fn get_all_possible_sudoku(&self) -> &Vec<SudokuMatrixValue>;
+
+    /// 恰好有一个解
+    fn is_unique(&mut self) -> bool {
+        self.count_solutions(2) == 1
     }
 }
 
 impl SudokuSolver {
     pub fn solver_possible(&mut self) {
-        if let Some((row, col)) = self.current.next_empty_value() {
-            let cans = self.candi.can_matrix[row][col].can;
-            for num in 0..SQUARE_OUTER_LEN {
-                if cans[num] && is_valid(&self.current, &(row, col), num + 1) {
-                    self.current.matrix[row][col] = num + 1;
-                    self.solver_possible();
+        self.count_solutions(usize::MAX);
+    }
+
+    pub fn get_all_possible_sudoku(&self) -> &Vec<SudokuMatrixValue> {
+        &self.all_possible
+    }
+
+    /// 以基础技巧推演至无进展 出现矛盾返回 false
+    fn propagate(can: &mut CandidateMatrix) -> bool {
+        loop {
+            let origin = *can;
+            can.evolution();
+            can.evolution_by_check_position();
+            if can.check().is_err() {
+                return false;
+            }
+            if origin == *can {
+                return true;
+            }
+        }
+    }
+
+    /// 候选值最少的未确定位置
+    fn most_constrained(can: &CandidateMatrix) -> Option<(usize, usize)> {
+        let mut chosen = None;
+        let mut min_count = SQUARE_OUTER_LEN + 1;
+        for (row, ll) in can.can_matrix.iter().enumerate() {
+            for (col, candidate) in ll.iter().enumerate() {
+                let count = candidate.count();
+                if count > 1 && count < min_count {
+                    min_count = count;
+                    chosen = Some((row, col));
                 }
             }
-            self.current.matrix[row][col] = SUDOKU_UNKNOWN;
-        } else {
-            self.all_possible.push(self.current);
         }
+        chosen
     }
 
-    pub fn get_all_possible_sudoku(&self) -> &Vec<SudokuMatrixValue> {
-        &self.all_possible
+    /// 每次猜测后重新推演 再选择候选值最少的位置继续猜测
+    fn search(&mut self, mut can: CandidateMatrix, limit: usize) {
+        if self.all_possible.len() >= limit || !SudokuSolver::propagate(&mut can) {
+            return;
+        }
+        let Some((row, col)) = SudokuSolver::most_constrained(&can) else {
+            self.all_possible.push(can.into());
+            return;
+        };
+        for value_id in can.can_matrix[row][col].iter() {
+            let mut next = can;
+            next.can_matrix[row][col] = Candidate::new_only(value_id);
+            self.search(next, limit);
+            if self.all_possible.len() >= limit {
+                break;
+            }
+        }
+    }
+}
+
+impl Solver for SudokuSolver {
+    fn count_solutions(&mut self, limit: usize) -> usize {
+        self.all_possible.clear();
+        self.search(self.candi, limit);
+        self.all_possible.len()
+    }
+
+    fn get_all_possible_sudoku(&self) -> &Vec<SudokuMatrixValue> {
+        SudokuSolver::get_all_possible_sudoku(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{
+        entity::SUDOKU_UNKNOWN,
+        fixture::{parse, HARD_SUDOKU},
+        rulers::init,
+    };
+
+    use super::*;
+
+    fn solver_of(sudoku: SudokuMatrixValue) -> SudokuSolver {
+        SudokuSolver::from(CandidateMatrix::from(sudoku))
+    }
+
+    #[test]
+    fn test_unique() {
+        init();
+
+        for (sudoku, solution) in HARD_SUDOKU {
+            let mut solver = solver_of(parse(sudoku));
+            assert!(solver.is_unique());
+            assert_eq!(solver.count_solutions(10), 1);
+            assert_eq!(solver.get_all_possible_sudoku(), &vec![parse(solution)]);
+        }
+    }
+
+    #[test]
+    fn test_inherent_api() {
+        init();
+
+        // 保留原有的固有方法 调用方无需引入 Solver
+        fn solve(mut solver: SudokuSolver) -> Vec<SudokuMatrixValue> {
+            solver.solver_possible();
+            solver.get_all_possible_sudoku().clone()
+        }
+        let (sudoku, solution) = HARD_SUDOKU[0];
+        assert_eq!(solve(solver_of(parse(sudoku))), vec![parse(solution)]);
+    }
+
+    #[test]
+    fn test_count_solutions_limit() {
+        init();
+
+        // 空白数独的解不计其数 找到足够数量后立即停止
+        let mut solver = solver_of(SudokuMatrixValue::new());
+        assert_eq!(solver.count_solutions(5), 5);
+        assert!(!solver.is_unique());
+        assert_eq!(solver.count_solutions(0), 0);
+        // 停止后仍可再次枚举
+        assert_eq!(solver.count_solutions(3), 3);
+    }
+
+    #[test]
+    fn test_multiple_solutions() {
+        init();
+
+        // 终盘中 (0,0) (0,2) (7,0) (7,2) 为 1 2 / 2 1 的矩形 去掉后可交换
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        for (row, col) in [(0, 0), (0, 2), (7, 0), (7, 2)] {
+            sudoku.matrix[row][col] = SUDOKU_UNKNOWN;
+        }
+        let mut solver = solver_of(sudoku);
+        assert_eq!(solver.count_solutions(10), 2);
+        assert!(!solver.is_unique());
+    }
+
+    #[test]
+    fn test_no_solution() {
+        init();
+
+        let mut sudoku = parse(HARD_SUDOKU[0].1);
+        sudoku.matrix[0][0] = SUDOKU_UNKNOWN;
+        sudoku.matrix[0][1] = 1;
+        let mut solver = solver_of(sudoku);
+        assert_eq!(solver.count_solutions(10), 0);
+        assert!(!solver.is_unique());
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::OnceLock};
 
 use super::entity::{SQUARE_INNER_LEN, SQUARE_INNER_NUM, SQUARE_OUTER_LEN};
 
@@ -13,6 +13,9 @@ pub struct SudokuRuler {
 }
 
 pub const RULER_COUNT: usize = 3;
+pub const ROW_RULER_ID: usize = 0;
+pub const COL_RULER_ID: usize = 1;
+pub const BOX_RULER_ID: usize = 2;
 /// 三大规则：行、列、九宫格内数字不重复
 pub type RulerLoop = [SudokuRuler; RULER_COUNT];
 fn gen_ruler_loop() -> RulerLoop {
@@ -23,14 +26,14 @@ fn gen_ruler_loop() -> RulerLoop {
     // row
     for row in 0..SQUARE_OUTER_LEN {
         for col in 0..SQUARE_OUTER_LEN {
-            ruler_loop[0].partitions[row][col] = (row, col);
+            ruler_loop[ROW_RULER_ID].partitions[row][col] = (row, col);
         }
     }
 
     // column
     for col in 0..SQUARE_OUTER_LEN {
         for row in 0..SQUARE_OUTER_LEN {
-            ruler_loop[1].partitions[col][row] = (row, col);
+            ruler_loop[COL_RULER_ID].partitions[col][row] = (row, col);
         }
     }
 
@@ -46,7 +49,7 @@ fn gen_ruler_loop() -> RulerLoop {
             let mut element_num = 0;
             for row in row_start..row_final {
                 for col in col_start..col_final {
-                    ruler_loop[2].partitions[each_num][element_num] = (row, col);
+                    ruler_loop[BOX_RULER_ID].partitions[each_num][element_num] = (row, col);
                     element_num += 1;
                 }
             }
@@ -83,19 +86,25 @@ struct RulerContainer {
     partition_map: RulerPartitionMap,
 }
 
-static mut RULER_CONTAINER: Option<RulerContainer> = None;
-pub fn init() {
-    let ruler_loop = gen_ruler_loop();
-    unsafe {
-        RULER_CONTAINER = Some(RulerContainer {
+/// 只初始化一次 之后只读 可在多个线程间共享
+static RULER_CONTAINER: OnceLock<RulerContainer> = OnceLock::new();
+fn get_container() -> &'static RulerContainer {
+    RULER_CONTAINER.get_or_init(|| {
+        let ruler_loop = gen_ruler_loop();
+        RulerContainer {
             ruler_loop,
             partition_map: gen_ruler_partition_map(&ruler_loop),
-        })
-    }
+        }
+    })
+}
+
+/// 提前生成规则 可重复调用
+pub fn init() {
+    get_container();
 }
 
 pub fn get_sudoku_ruler_loop() -> RulerLoop {
-    unsafe { RULER_CONTAINER.as_ref().unwrap().ruler_loop }
+    get_container().ruler_loop
 }
 
 pub fn each_sudoku_partition<F>(mut cb: F)
@@ -113,14 +122,7 @@ where
 pub fn get_sudoku_ruler_partition_map(
     pos: &Position,
 ) -> [[Position; SQUARE_OUTER_LEN]; RULER_COUNT] {
-    unsafe {
-        *RULER_CONTAINER
-            .as_ref()
-            .unwrap()
-            .partition_map
-            .get(pos)
-            .unwrap()
-    }
+    *get_container().partition_map.get(pos).unwrap()
 }
 
 #[cfg(test)]
@@ -157,4 +159,22 @@ mod tests {
             println!("{:?}", l);
         }
     }
+
+    #[test]
+    fn test_shared_across_threads() {
+        let expected = gen_ruler_loop()[BOX_RULER_ID].partitions[4];
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        init();
+                        get_sudoku_ruler_partition_map(&(4, 4))[BOX_RULER_ID]
+                    })
+                })
+                .collect();
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), expected);
+            }
+        });
+    }
 }